@@ -52,7 +52,7 @@ fn fuel_for_module_recursive(module_mass: i64) -> i64 {
     let mut fuel = fuel_for_mass(module_mass);
 
     while fuel > 0 {
-        tot = tot + fuel;
+        tot += fuel;
         fuel = fuel_for_mass(fuel);
     }
 
@@ -0,0 +1,289 @@
+use super::{run_intcode, set_input, Error, Program};
+use std::mem::discriminant;
+
+#[derive(Debug, PartialEq)]
+struct Reproducer {
+    program: Program,
+    inputs: Option<(i64, i64)>,
+}
+
+impl Reproducer {
+    fn run(&self) -> Result<Program, Error> {
+        match self.inputs {
+            Some((noun, verb)) => set_input(self.program.clone(), noun, verb).and_then(run_intcode),
+            None => run_intcode(self.program.clone()),
+        }
+    }
+
+    // Whether running this reproducer fails with the same kind of error as `target`. The
+    // variant's payload is ignored, so `UnknownOpCode(5)` counts as the same fault as
+    // `UnknownOpCode(100)`.
+    fn fails_like(&self, target: &Error) -> bool {
+        match self.run() {
+            Err(e) => discriminant(&e) == discriminant(target),
+            Ok(_) => false,
+        }
+    }
+}
+
+pub fn run(program: Program, inputs: Option<(i64, i64)>) {
+    let original_len = program.len();
+    let reproducer = Reproducer { program, inputs };
+
+    let target = match reproducer.run() {
+        Ok(_) => {
+            println!("The program runs without error, so there is nothing to minimize");
+            return;
+        }
+        Err(e) => e,
+    };
+
+    let minimal = minimize(reproducer, &target);
+    let error = minimal.run().unwrap_err();
+
+    println!("Original error: {}", target);
+    println!("Minimal error: {}", error);
+    println!(
+        "Reduced program from {} to {} values:",
+        original_len,
+        minimal.program.len()
+    );
+    println!(
+        "{}",
+        minimal
+            .program
+            .iter()
+            .map(i64::to_string)
+            .collect::<Vec<_>>()
+            .join(",")
+    );
+    if let Some((noun, verb)) = minimal.inputs {
+        println!("With inputs: noun={} verb={}", noun, verb);
+    }
+}
+
+// Shrinks a failing reproducer until no single reduction step keeps the same kind of error.
+// Removing chunks of the program is tried first, then the remaining values are pulled towards
+// zero, and this repeats until neither makes progress.
+fn minimize(mut reproducer: Reproducer, target: &Error) -> Reproducer {
+    reproducer = fold_inputs(reproducer, target);
+
+    loop {
+        let before = reproducer.program.clone();
+        reproducer = remove_chunks(reproducer, target);
+        reproducer = simplify_values(reproducer, target);
+        if reproducer.program == before {
+            return reproducer;
+        }
+    }
+}
+
+// Writes the noun and verb into the program itself, which leaves fewer moving parts to shrink.
+// This is skipped when setting the inputs is itself what fails.
+fn fold_inputs(reproducer: Reproducer, target: &Error) -> Reproducer {
+    let (noun, verb) = match reproducer.inputs {
+        Some(inputs) => inputs,
+        None => return reproducer,
+    };
+    let folded = match set_input(reproducer.program.clone(), noun, verb) {
+        Ok(program) => Reproducer {
+            program,
+            inputs: None,
+        },
+        Err(_) => return reproducer,
+    };
+
+    if folded.fails_like(target) {
+        folded
+    } else {
+        reproducer
+    }
+}
+
+// Delta debugging over the program: repeatedly tries dropping one of `n` equal chunks, and
+// splits into finer chunks whenever no chunk can be dropped.
+fn remove_chunks(mut reproducer: Reproducer, target: &Error) -> Reproducer {
+    let mut n = 2;
+
+    while !reproducer.program.is_empty() {
+        let len = reproducer.program.len();
+        let chunk = len.div_ceil(n);
+        let mut reduced = false;
+
+        for start in (0..len).step_by(chunk) {
+            let end = (start + chunk).min(len);
+            let mut program = reproducer.program[..start].to_vec();
+            program.extend_from_slice(&reproducer.program[end..]);
+            let candidate = Reproducer {
+                program,
+                inputs: reproducer.inputs,
+            };
+            if candidate.fails_like(target) {
+                reproducer = candidate;
+                n = (n - 1).max(2);
+                reduced = true;
+                break;
+            }
+        }
+
+        if !reduced {
+            if chunk == 1 {
+                break;
+            }
+            n = (n * 2).min(len);
+        }
+    }
+
+    reproducer
+}
+
+// Moves each value, and then each input, as close to zero as possible by trying zero first and
+// then repeatedly halving.
+fn simplify_values(mut reproducer: Reproducer, target: &Error) -> Reproducer {
+    for i in 0..reproducer.program.len() {
+        let original = reproducer.program[i];
+        reproducer.program[i] = shrink(original, |value| {
+            let mut program = reproducer.program.clone();
+            program[i] = value;
+            Reproducer {
+                program,
+                inputs: reproducer.inputs,
+            }
+            .fails_like(target)
+        });
+    }
+
+    if let Some((noun, verb)) = reproducer.inputs {
+        let noun = shrink(noun, |noun| {
+            Reproducer {
+                program: reproducer.program.clone(),
+                inputs: Some((noun, verb)),
+            }
+            .fails_like(target)
+        });
+        let verb = shrink(verb, |verb| {
+            Reproducer {
+                program: reproducer.program.clone(),
+                inputs: Some((noun, verb)),
+            }
+            .fails_like(target)
+        });
+        reproducer.inputs = Some((noun, verb));
+    }
+
+    reproducer
+}
+
+fn shrink<F>(value: i64, still_fails: F) -> i64
+where
+    F: Fn(i64) -> bool,
+{
+    if value == 0 || still_fails(0) {
+        return 0;
+    }
+
+    let mut best = value;
+    let mut candidate = value / 2;
+    while candidate != 0 && candidate != best {
+        if still_fails(candidate) {
+            best = candidate;
+        }
+        candidate /= 2;
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day2::OpCode;
+
+    #[test]
+    fn test_fails_like() {
+        let reproducer = Reproducer {
+            program: vec![1, 0, 0, 0, 5],
+            inputs: None,
+        };
+        assert!(reproducer.fails_like(&Error::UnknownOpCode(100)));
+        assert!(!reproducer.fails_like(&Error::SegFault));
+
+        let reproducer = Reproducer {
+            program: vec![1, 0, 0, 0, 99],
+            inputs: None,
+        };
+        assert!(!reproducer.fails_like(&Error::UnknownOpCode(100)));
+    }
+
+    #[test]
+    fn test_shrink() {
+        assert_eq!(0, shrink(100, |_| true));
+        assert_eq!(100, shrink(100, |v| v == 100));
+        assert_eq!(6, shrink(100, |v| v > 5));
+        assert_eq!(-3, shrink(-100, |v| v < -2));
+    }
+
+    #[test]
+    fn test_fold_inputs() {
+        let reproducer = Reproducer {
+            program: vec![1, 0, 0, 0, 99],
+            inputs: Some((50, 0)),
+        };
+        assert_eq!(
+            Reproducer {
+                program: vec![1, 50, 0, 0, 99],
+                inputs: None,
+            },
+            fold_inputs(reproducer, &Error::SegFault)
+        );
+
+        let reproducer = Reproducer {
+            program: vec![1, 0],
+            inputs: Some((50, 0)),
+        };
+        assert_eq!(
+            Reproducer {
+                program: vec![1, 0],
+                inputs: Some((50, 0)),
+            },
+            fold_inputs(reproducer, &Error::ProgramTooShort)
+        );
+    }
+
+    #[test]
+    fn test_minimize() {
+        // A long run of harmless additions followed by a bad op code
+        let mut program = vec![];
+        for _ in 0..20 {
+            program.extend_from_slice(&[1, 0, 0, 3]);
+        }
+        program.extend_from_slice(&[42, 7, 7, 7, 99]);
+        let reproducer = Reproducer {
+            program,
+            inputs: None,
+        };
+        let minimal = minimize(reproducer, &Error::UnknownOpCode(42));
+        assert_eq!(vec![0], minimal.program);
+        assert_eq!(Err(Error::UnknownOpCode(0)), minimal.run());
+
+        let reproducer = Reproducer {
+            program: vec![1, 0, 0, 0, 2, 0, 0, 0, 2, 9, 9, 50, 99, 30, 40, 50],
+            inputs: Some((4, 4)),
+        };
+        let minimal = minimize(reproducer, &Error::SegFault);
+        assert_eq!(
+            Reproducer {
+                program: vec![1, 0, 0, 6],
+                inputs: None,
+            },
+            minimal
+        );
+
+        let reproducer = Reproducer {
+            program: vec![2, 0, 0, 0, 1, 1, 1],
+            inputs: None,
+        };
+        let minimal = minimize(reproducer, &Error::NotEnoughParams(OpCode::Add));
+        assert_eq!(vec![1], minimal.program);
+        assert_eq!(Err(Error::NotEnoughParams(OpCode::Add)), minimal.run());
+    }
+}
@@ -0,0 +1,520 @@
+use super::screen::{AsciiSink, TileSink};
+use super::{Memory, Program};
+use crate::rng::Rng;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::Range;
+use std::path::Path;
+use std::rc::Rc;
+
+// A peripheral that owns a range of addresses. Offsets are relative to the start of the range,
+// and every read or write the program makes in that range goes to the device instead of memory.
+pub trait Device {
+    fn read(&mut self, offset: usize) -> i64;
+    fn write(&mut self, offset: usize, value: i64);
+}
+
+// Lets a caller keep a handle on a device after attaching it, so it can be inspected once the
+// program stops.
+impl<D: Device> Device for Rc<RefCell<D>> {
+    fn read(&mut self, offset: usize) -> i64 {
+        self.borrow_mut().read(offset)
+    }
+
+    fn write(&mut self, offset: usize, value: i64) {
+        self.borrow_mut().write(offset, value)
+    }
+}
+
+// Devices have to end at or below this address, which keeps memory small enough to allocate
+pub const ADDRESS_LIMIT: usize = 1 << 24;
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    EmptyRange,
+    TooLarge,
+    Overlap(usize),
+    BadSpec(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::EmptyRange => write!(f, "A device must cover at least one address"),
+            Error::TooLarge => write!(f, "A device must end at or below address {}", ADDRESS_LIMIT),
+            Error::Overlap(addr) => write!(f, "Address {} is already mapped to a device", addr),
+            Error::BadSpec(spec) => write!(f, "Could not understand device '{}'", spec),
+        }
+    }
+}
+
+// Program memory with devices attached to some of its addresses. Attaching a device past the
+// end of the program extends memory with zeroes up to the end of the device's range.
+pub struct Bus {
+    ram: Program,
+    mappings: Vec<(Range<usize>, Box<dyn Device>)>,
+}
+
+impl Bus {
+    pub fn new(ram: Program) -> Bus {
+        Bus {
+            ram,
+            mappings: vec![],
+        }
+    }
+
+    pub fn attach(
+        &mut self,
+        start: usize,
+        size: usize,
+        device: Box<dyn Device>,
+    ) -> Result<(), Error> {
+        if size == 0 {
+            return Err(Error::EmptyRange);
+        }
+        let end = start
+            .checked_add(size)
+            .filter(|&end| end <= ADDRESS_LIMIT)
+            .ok_or(Error::TooLarge)?;
+        let range = start..end;
+        for (mapped, _) in self.mappings.iter() {
+            if range.start < mapped.end && mapped.start < range.end {
+                return Err(Error::Overlap(range.start.max(mapped.start)));
+            }
+        }
+
+        if self.ram.len() < range.end {
+            self.ram.resize(range.end, 0);
+        }
+        self.mappings.push((range, device));
+
+        Ok(())
+    }
+
+    pub fn run(&mut self) -> Result<(), super::Error> {
+        super::run(self)
+    }
+
+    // The contents of memory outside any device
+    pub fn ram(&self) -> &Program {
+        &self.ram
+    }
+
+    fn device_at(&mut self, addr: usize) -> Option<(&mut Box<dyn Device>, usize)> {
+        self.mappings
+            .iter_mut()
+            .find(|(range, _)| range.contains(&addr))
+            .map(|(range, device)| (device, addr - range.start))
+    }
+}
+
+impl Memory for Bus {
+    fn size(&self) -> usize {
+        self.ram.len()
+    }
+
+    fn read(&mut self, addr: usize) -> i64 {
+        match self.device_at(addr) {
+            Some((device, offset)) => device.read(offset),
+            None => self.ram[addr],
+        }
+    }
+
+    fn write(&mut self, addr: usize, value: i64) {
+        match self.device_at(addr) {
+            Some((device, offset)) => device.write(offset, value),
+            None => self.ram[addr] = value,
+        }
+    }
+}
+
+// A single address that reads characters from an input queue and collects what is written to it.
+// Reads return 0 once the input runs out.
+pub struct Console {
+    input: VecDeque<i64>,
+    output: Vec<i64>,
+}
+
+impl Console {
+    pub fn new(input: &str) -> Console {
+        Console {
+            input: input.chars().map(|c| c as i64).collect(),
+            output: vec![],
+        }
+    }
+
+    pub fn output(&self) -> String {
+        self.output
+            .iter()
+            .map(|&v| {
+                u32::try_from(v)
+                    .ok()
+                    .and_then(std::char::from_u32)
+                    .unwrap_or(std::char::REPLACEMENT_CHARACTER)
+            })
+            .collect()
+    }
+}
+
+impl Device for Console {
+    fn read(&mut self, _: usize) -> i64 {
+        self.input.pop_front().unwrap_or(0)
+    }
+
+    fn write(&mut self, _: usize, value: i64) {
+        self.output.push(value);
+    }
+}
+
+// A single address that counts up by one every time it is read, wrapping round past the largest
+// value. Writing sets the count.
+#[derive(Default)]
+pub struct Clock {
+    ticks: i64,
+}
+
+impl Device for Clock {
+    fn read(&mut self, _: usize) -> i64 {
+        let ticks = self.ticks;
+        self.ticks = self.ticks.wrapping_add(1);
+        ticks
+    }
+
+    fn write(&mut self, _: usize, value: i64) {
+        self.ticks = value;
+    }
+}
+
+// A single address that gives a new non-negative random number every time it is read. Writing
+// reseeds it.
+pub struct Random {
+    rng: Rng,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Random {
+        Random {
+            rng: Rng::new(seed),
+        }
+    }
+}
+
+impl Device for Random {
+    fn read(&mut self, _: usize) -> i64 {
+        (self.rng.next_u64() >> 1) as i64
+    }
+
+    fn write(&mut self, _: usize, value: i64) {
+        self.rng = Rng::new(value as u64);
+    }
+}
+
+// A grid of pixels laid out row by row, one address per pixel.
+pub struct Framebuffer {
+    width: usize,
+    pixels: Vec<i64>,
+}
+
+impl Framebuffer {
+    pub fn new(width: usize, height: usize) -> Result<Framebuffer, Error> {
+        let size = width
+            .checked_mul(height)
+            .filter(|&size| size <= ADDRESS_LIMIT)
+            .ok_or(Error::TooLarge)?;
+        if size == 0 {
+            return Err(Error::EmptyRange);
+        }
+        Ok(Framebuffer {
+            width,
+            pixels: vec![0; size],
+        })
+    }
+
+    pub fn size(&self) -> usize {
+        self.pixels.len()
+    }
+
+    // Draws unset pixels as `.` and any other value as `#`
+    pub fn render(&self) -> String {
+        self.pixels
+            .chunks(self.width)
+            .map(|row| {
+                row.iter()
+                    .map(|&p| if p == 0 { '.' } else { '#' })
+                    .collect::<String>()
+                    + "\n"
+            })
+            .collect()
+    }
+}
+
+impl Device for Framebuffer {
+    fn read(&mut self, offset: usize) -> i64 {
+        self.pixels[offset]
+    }
+
+    fn write(&mut self, offset: usize, value: i64) {
+        self.pixels[offset] = value;
+    }
+}
+
+// Runs a program with devices described by specs such as `console@100`, `clock@101`,
+// `random@102`, `framebuffer@200:16x8`, `tiles@300` or `ascii@301`, then shows what the devices
+// ended up holding. The screen drawn by a `tiles` device can also be saved as an image.
+pub fn run(program: Program, specs: &[&str], input: &str, seed: u64, image: Option<&str>) {
+    let mut bus = Bus::new(program);
+    let mut consoles = vec![];
+    let mut framebuffers = vec![];
+    let mut tile_sinks = vec![];
+    let mut ascii_sinks = vec![];
+
+    for spec in specs {
+        let attached = match parse_spec(spec) {
+            Ok(("console", addr, None)) => {
+                let console = Rc::new(RefCell::new(Console::new(input)));
+                consoles.push((addr, Rc::clone(&console)));
+                bus.attach(addr, 1, Box::new(console))
+            }
+            Ok(("clock", addr, None)) => bus.attach(addr, 1, Box::new(Clock::default())),
+            Ok(("random", addr, None)) => bus.attach(addr, 1, Box::new(Random::new(seed))),
+            Ok(("framebuffer", addr, Some((width, height)))) => Framebuffer::new(width, height)
+                .and_then(|framebuffer| {
+                    let size = framebuffer.size();
+                    let framebuffer = Rc::new(RefCell::new(framebuffer));
+                    framebuffers.push((addr, Rc::clone(&framebuffer)));
+                    bus.attach(addr, size, Box::new(framebuffer))
+                }),
+            Ok(("tiles", addr, None)) => {
+                let sink = Rc::new(RefCell::new(TileSink::default()));
+                tile_sinks.push((addr, Rc::clone(&sink)));
+                bus.attach(addr, 1, Box::new(sink))
+            }
+            Ok(("ascii", addr, None)) => {
+                let sink = Rc::new(RefCell::new(AsciiSink::default()));
+                ascii_sinks.push((addr, Rc::clone(&sink)));
+                bus.attach(addr, 1, Box::new(sink))
+            }
+            Ok(_) => Err(Error::BadSpec(spec.to_string())),
+            Err(why) => Err(why),
+        };
+        if let Err(why) = attached {
+            println!("{}", why);
+            return;
+        }
+    }
+    if image.is_some() && tile_sinks.len() != 1 {
+        println!("Saving an image needs exactly one tiles device");
+        return;
+    }
+
+    match bus.run() {
+        Ok(()) => println!("Output is: {}", bus.ram()[0]),
+        Err(e) => println!("{}", e),
+    }
+    for (addr, console) in consoles {
+        println!("Console at {}:\n{}", addr, console.borrow().output());
+    }
+    for (addr, framebuffer) in framebuffers {
+        print!(
+            "Framebuffer at {}:\n{}",
+            addr,
+            framebuffer.borrow().render()
+        );
+    }
+    for (addr, sink) in ascii_sinks {
+        let sink = sink.borrow();
+        println!("ASCII output at {}:\n{}", addr, sink.text());
+        for value in sink.other() {
+            println!("Non-ASCII value at {}: {}", addr, value);
+        }
+    }
+    for (addr, sink) in tile_sinks.iter() {
+        match sink.borrow().screen().render() {
+            Ok(screen) => print!("Screen at {}:\n{}", addr, screen),
+            Err(why) => println!("Screen at {}: {}", addr, why),
+        }
+    }
+    if let (Some(path), Some((_, sink))) = (image, tile_sinks.first()) {
+        match sink.borrow().screen().save(Path::new(path)) {
+            Ok(()) => println!("Saved screen to {}", path),
+            Err(why) => println!("Couldn't save screen to {}: {}", path, why),
+        }
+    }
+}
+
+type Dimensions = (usize, usize);
+
+// Splits `kind@address` or `kind@address:WIDTHxHEIGHT` into its parts.
+fn parse_spec(spec: &str) -> Result<(&str, usize, Option<Dimensions>), Error> {
+    let bad_spec = || Error::BadSpec(spec.to_string());
+
+    let mut parts = spec.splitn(2, '@');
+    let kind = parts.next().unwrap();
+    let location = parts.next().ok_or_else(bad_spec)?;
+
+    let mut parts = location.splitn(2, ':');
+    let addr = parts
+        .next()
+        .unwrap()
+        .parse::<usize>()
+        .map_err(|_| bad_spec())?;
+    let dimensions = match parts.next() {
+        None => None,
+        Some(dimensions) => {
+            let mut parts = dimensions.splitn(2, 'x');
+            let width = parts.next().unwrap().parse::<usize>();
+            let height = parts.next().ok_or_else(bad_spec)?.parse::<usize>();
+            match (width, height) {
+                (Ok(width), Ok(height)) if width > 0 && height > 0 => Some((width, height)),
+                _ => return Err(bad_spec()),
+            }
+        }
+    };
+
+    Ok((kind, addr, dimensions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day2;
+
+    #[test]
+    fn test_attach() {
+        let mut bus = Bus::new(vec![99]);
+        assert_eq!(
+            Err(Error::EmptyRange),
+            bus.attach(5, 0, Box::new(Clock::default()))
+        );
+        let framebuffer = Framebuffer::new(2, 2).unwrap();
+        assert_eq!(Ok(()), bus.attach(5, 4, Box::new(framebuffer)));
+        assert_eq!(9, bus.size());
+        assert_eq!(
+            Err(Error::Overlap(8)),
+            bus.attach(8, 1, Box::new(Clock::default()))
+        );
+        assert_eq!(
+            Err(Error::Overlap(5)),
+            bus.attach(3, 3, Box::new(Clock::default()))
+        );
+        assert_eq!(Ok(()), bus.attach(9, 1, Box::new(Clock::default())));
+        assert_eq!(Ok(()), bus.attach(4, 1, Box::new(Clock::default())));
+
+        // Ranges past the limit, or that don't fit in an address at all, aren't mapped
+        assert_eq!(
+            Err(Error::TooLarge),
+            bus.attach(usize::MAX, 1, Box::new(Clock::default()))
+        );
+        assert_eq!(
+            Err(Error::TooLarge),
+            bus.attach(ADDRESS_LIMIT, 1, Box::new(Clock::default()))
+        );
+        assert_eq!(10, bus.size());
+    }
+
+    #[test]
+    fn test_bus_routes_to_devices() {
+        let framebuffer = Rc::new(RefCell::new(Framebuffer::new(2, 1).unwrap()));
+        let mut bus = Bus::new(vec![0, 0, 0]);
+        bus.attach(1, 2, Box::new(Rc::clone(&framebuffer))).unwrap();
+
+        bus.write(0, 7);
+        bus.write(2, 9);
+        assert_eq!(7, bus.read(0));
+        assert_eq!(9, bus.read(2));
+        assert_eq!(&vec![7, 0, 0], bus.ram());
+        assert_eq!(vec![0, 9], framebuffer.borrow().pixels);
+    }
+
+    #[test]
+    fn test_run_with_console() {
+        // Adds 40 and 32 and writes the result, 'H', to the console at address 8
+        let console = Rc::new(RefCell::new(Console::new("")));
+        let mut bus = Bus::new(vec![1, 5, 6, 8, 99, 40, 32, 0]);
+        bus.attach(8, 1, Box::new(Rc::clone(&console))).unwrap();
+        assert_eq!(Ok(()), bus.run());
+        assert_eq!("H", console.borrow().output());
+
+        // Reads two characters from the console and adds them into position 0
+        let console = Rc::new(RefCell::new(Console::new("AB")));
+        let mut bus = Bus::new(vec![1, 5, 5, 0, 99]);
+        bus.attach(5, 1, Box::new(Rc::clone(&console))).unwrap();
+        assert_eq!(Ok(()), bus.run());
+        assert_eq!(65 + 66, bus.ram()[0]);
+    }
+
+    #[test]
+    fn test_run_faults_outside_bus() {
+        let mut bus = Bus::new(vec![1, 0, 0, 9, 99]);
+        bus.attach(5, 2, Box::new(Clock::default())).unwrap();
+        assert_eq!(Err(day2::Error::SegFault), bus.run());
+    }
+
+    #[test]
+    fn test_clock() {
+        let mut clock = Clock::default();
+        assert_eq!(0, clock.read(0));
+        assert_eq!(1, clock.read(0));
+        clock.write(0, 10);
+        assert_eq!(10, clock.read(0));
+        clock.write(0, i64::MAX);
+        assert_eq!(i64::MAX, clock.read(0));
+        assert_eq!(i64::MIN, clock.read(0));
+    }
+
+    #[test]
+    fn test_random() {
+        let mut a = Random::new(3);
+        let mut b = Random::new(3);
+        for _ in 0..10 {
+            let n = a.read(0);
+            assert!(n >= 0);
+            assert_eq!(n, b.read(0));
+        }
+        a.write(0, 5);
+        b.write(0, 5);
+        assert_eq!(a.read(0), b.read(0));
+    }
+
+    #[test]
+    fn test_framebuffer_render() {
+        let mut framebuffer = Framebuffer::new(3, 2).unwrap();
+        framebuffer.write(0, 1);
+        framebuffer.write(4, 2);
+        assert_eq!("#..\n.#.\n", framebuffer.render());
+        assert!(Framebuffer::new(usize::MAX, 2).is_err());
+        assert!(Framebuffer::new(ADDRESS_LIMIT, 2).is_err());
+    }
+
+    #[test]
+    fn test_console_output() {
+        let mut console = Console::new("");
+        for &v in [72, 105, -1].iter() {
+            console.write(0, v);
+        }
+        assert_eq!("Hi\u{FFFD}", console.output());
+    }
+
+    #[test]
+    fn test_parse_spec() {
+        assert_eq!(Ok(("console", 100, None)), parse_spec("console@100"));
+        assert_eq!(
+            Ok(("framebuffer", 200, Some((16, 8)))),
+            parse_spec("framebuffer@200:16x8")
+        );
+        assert_eq!(
+            Err(Error::BadSpec("console".to_string())),
+            parse_spec("console")
+        );
+        assert_eq!(
+            Err(Error::BadSpec("clock@x".to_string())),
+            parse_spec("clock@x")
+        );
+        assert_eq!(
+            Err(Error::BadSpec("framebuffer@1:0x2".to_string())),
+            parse_spec("framebuffer@1:0x2")
+        );
+    }
+}
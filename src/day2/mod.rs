@@ -1,7 +1,13 @@
 use std::fmt;
 
-#[derive(Debug, PartialEq)]
-enum OpCode {
+pub mod minimize;
+pub mod mmio;
+pub mod optimize;
+pub mod screen;
+pub mod suite;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OpCode {
     Add,
     Multiply,
     Exit,
@@ -20,7 +26,7 @@ impl From<i64> for OpCode {
 }
 
 #[derive(Debug, PartialEq)]
-enum Error {
+pub enum Error {
     ProgramTooShort,
     NotEnoughParams(OpCode),
     SegFault,
@@ -49,10 +55,17 @@ pub fn part1(input: Program) {
 
 pub fn part2(input: Program) {
     let target = 19690720;
+    // The optimized program runs faster, but is only used if it behaves like the original
+    let program = match optimize::optimize(&input) {
+        Ok(optimized) if optimize::check_equivalence(&input, &optimized, 1000, 1).is_ok() => {
+            optimized.program
+        }
+        _ => input,
+    };
 
     for n in 0..=99 {
         for v in 0..=99 {
-            if let Ok(output) = execute(&input, n, v) {
+            if let Ok(output) = execute(&program, n, v) {
                 if output == target {
                     println!("Found input values: noun={} verb={}", n, v);
                     println!("100 * {} + {} = {}", n, v, 100 * n + v);
@@ -72,7 +85,7 @@ fn execute(input: &Program, noun: i64, verb: i64) -> Result<i64, Error> {
 
     set_input(memory, noun, verb)
         .and_then(run_intcode)
-        .and_then(|mem| Ok(mem[0]))
+        .map(|mem| mem[0])
 }
 
 const NOUN_INDEX: usize = 1;
@@ -89,8 +102,35 @@ fn set_input(mut prog: Program, noun: i64, verb: i64) -> Result<Program, Error>
     Ok(prog)
 }
 
+// Storage that the interpreter reads instructions and data from. A program is its own memory,
+// but other implementations can intercept some addresses, as the device bus in `mmio` does.
+trait Memory {
+    fn size(&self) -> usize;
+    fn read(&mut self, addr: usize) -> i64;
+    fn write(&mut self, addr: usize, value: i64);
+}
+
+impl Memory for Program {
+    fn size(&self) -> usize {
+        self.len()
+    }
+
+    fn read(&mut self, addr: usize) -> i64 {
+        self[addr]
+    }
+
+    fn write(&mut self, addr: usize, value: i64) {
+        self[addr] = value;
+    }
+}
+
 fn run_intcode(mut prog: Program) -> Result<Program, Error> {
-    let len = prog.len();
+    run(&mut prog)?;
+    Ok(prog)
+}
+
+fn run<M: Memory>(mem: &mut M) -> Result<(), Error> {
+    let len = mem.size();
     if len < 1 {
         return Err(Error::ProgramTooShort);
     }
@@ -98,39 +138,42 @@ fn run_intcode(mut prog: Program) -> Result<Program, Error> {
     let mut i = 0;
 
     while i < len {
-        match OpCode::from(prog[i]) {
+        let op_code = mem.read(i);
+        match OpCode::from(op_code) {
             OpCode::Add => {
                 if (i + 3) >= len {
                     return Err(Error::NotEnoughParams(OpCode::Add));
                 }
-                let src1 = prog[i + 1] as usize;
-                let src2 = prog[i + 2] as usize;
-                let dest = prog[i + 3] as usize;
+                let src1 = mem.read(i + 1) as usize;
+                let src2 = mem.read(i + 2) as usize;
+                let dest = mem.read(i + 3) as usize;
                 if src1 >= len || src2 >= len || dest >= len {
                     return Err(Error::SegFault);
                 }
-                prog[dest] = prog[src1] + prog[src2];
+                let value = mem.read(src1) + mem.read(src2);
+                mem.write(dest, value);
             }
             OpCode::Multiply => {
                 if (i + 3) >= len {
                     return Err(Error::NotEnoughParams(OpCode::Multiply));
                 }
-                let src1 = prog[i + 1] as usize;
-                let src2 = prog[i + 2] as usize;
-                let dest = prog[i + 3] as usize;
+                let src1 = mem.read(i + 1) as usize;
+                let src2 = mem.read(i + 2) as usize;
+                let dest = mem.read(i + 3) as usize;
                 if src1 >= len || src2 >= len || dest >= len {
                     return Err(Error::SegFault);
                 }
-                prog[dest] = prog[src1] * prog[src2];
+                let value = mem.read(src1) * mem.read(src2);
+                mem.write(dest, value);
             }
-            OpCode::Exit => return Ok(prog),
-            OpCode::Unknown => return Err(Error::UnknownOpCode(prog[i])),
+            OpCode::Exit => return Ok(()),
+            OpCode::Unknown => return Err(Error::UnknownOpCode(op_code)),
         }
 
-        i = i + 4;
+        i += 4;
     }
 
-    Ok(prog)
+    Ok(())
 }
 
 #[cfg(test)]
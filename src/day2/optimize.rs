@@ -0,0 +1,737 @@
+use super::{run_intcode, set_input, OpCode, Program};
+use crate::rng::Rng;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::ops::Range;
+
+// The optimizer follows a program the way `run_intcode` runs it after `set_input`: the noun and
+// verb are unknown, and every other cell starts as the program gives it. Programs run straight
+// through four cells at a time, so the instructions executed can be listed by following the
+// program from the start, including instructions that earlier ones rewrite with known values.
+// That list is then rewritten a step at a time:
+//
+// - constant folding replaces reads of cells whose values are known with the values, and
+//   instructions whose operands are all known with their results
+// - chain shortening joins an addition or multiplication by a constant onto the one before it
+//   that fed it, so `t = x + 2; u = t + 3` becomes `u = x + 5`
+// - dead store removal drops instructions whose results are overwritten before they're read, or
+//   are never seen at all
+//
+// and the instructions left are laid out again where the original instructions were.
+//
+// The optimized program doesn't keep all of the final memory. Every cell outside the laid out
+// instructions is left with the value the original program leaves in it, including position 0 and
+// any data after the exit, but the cells the instructions take up, `Optimized::rewritten`, end up
+// holding the new instructions instead of whatever the original left there, like results it wrote
+// over instructions already run. `check_equivalence` compares the cells outside them only.
+//
+// The first instruction reads the noun and verb as addresses, so it stays where it is and still
+// faults for the same inputs. Other instructions that read through addresses only known at run
+// time are kept for the same reason, as long as nothing uses what they read, since they could read
+// the rewritten cells. If the program writes an op code or a destination that depends on the
+// inputs, what it does next can't be followed, and it's left as it is.
+
+// Where the instructions after the first start
+const CODE_START: usize = 4;
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    DynamicOpCode(usize),
+    DynamicDestination(usize),
+    AlwaysFails(usize),
+    UsesDynamicRead(usize),
+    FirstWritesCode(usize),
+    NoRoom(usize),
+    NoSaving,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::DynamicOpCode(i) => write!(f, "The op code at {} depends on the inputs", i),
+            Error::DynamicDestination(i) => write!(
+                f,
+                "The instruction at {} writes to an address that depends on the inputs",
+                i
+            ),
+            Error::AlwaysFails(i) => write!(f, "The instruction at {} always fails", i),
+            Error::UsesDynamicRead(i) => write!(
+                f,
+                "The value the instruction at {} reads through the inputs is used",
+                i
+            ),
+            Error::FirstWritesCode(addr) => write!(
+                f,
+                "The first instruction writes to {}, where the optimized instructions would go",
+                addr
+            ),
+            Error::NoRoom(len) => write!(
+                f,
+                "The optimized instructions would need {} values, more than the original's",
+                len
+            ),
+            Error::NoSaving => write!(f, "There are no instructions to save"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Operand {
+    // The value in a cell
+    Cell(usize),
+    // A value known before the program runs
+    Const(i64),
+    // The value at the address held in a cell, which is only known when the program runs
+    Pointer(usize),
+}
+
+// An instruction as executed, with `at` where it was in the original program
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Step {
+    at: usize,
+    op: OpCode,
+    args: [Operand; 2],
+    dest: usize,
+}
+
+impl Step {
+    // What the step writes, if its operands are all known
+    fn value(&self) -> Option<i64> {
+        match self.args {
+            [Operand::Const(a), Operand::Const(b)] if self.op == OpCode::Add => a.checked_add(b),
+            [Operand::Const(a), Operand::Const(b)] => a.checked_mul(b),
+            _ => None,
+        }
+    }
+
+    fn reads_pointer(&self) -> bool {
+        self.args
+            .iter()
+            .any(|arg| matches!(arg, Operand::Pointer(_)))
+    }
+
+    // The cell and the constant, for a step that works a cell's value and a constant
+    fn split_constant(&self) -> Option<(usize, i64)> {
+        match self.args {
+            [Operand::Cell(cell), Operand::Const(c)] | [Operand::Const(c), Operand::Cell(cell)] => {
+                Some((cell, c))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Optimized {
+    pub program: Program,
+    pub original_steps: usize,
+    pub optimized_steps: usize,
+    // The cells holding the optimized instructions, which end up different from the original's
+    pub rewritten: Range<usize>,
+}
+
+pub fn run(input: Program, trials: usize, seed: u64) {
+    let optimized = match optimize(&input) {
+        Ok(optimized) => optimized,
+        Err(why) => {
+            println!("Left the program as it is: {}", why);
+            return;
+        }
+    };
+
+    println!(
+        "{}",
+        optimized
+            .program
+            .iter()
+            .map(i64::to_string)
+            .collect::<Vec<_>>()
+            .join(",")
+    );
+    println!(
+        "Instructions executed per run: {} before, {} after",
+        optimized.original_steps, optimized.optimized_steps
+    );
+    match check_equivalence(&input, &optimized, trials, seed) {
+        Ok(()) => println!(
+            "Memory outside positions {} to {} matched on {} random noun/verb pairs",
+            optimized.rewritten.start,
+            optimized.rewritten.end - 1,
+            trials
+        ),
+        Err((noun, verb)) => println!("Results differ for noun={} verb={}", noun, verb),
+    }
+}
+
+pub fn optimize(prog: &Program) -> Result<Optimized, Error> {
+    let trace = trace(prog)?;
+    if trace.end <= CODE_START {
+        return Err(Error::NoSaving);
+    }
+    let rewritten = CODE_START..trace.end;
+
+    let steps = shorten(fold(&trace.steps, prog));
+    let steps = remove_dead(&steps, &rewritten, prog.len())?;
+    let mut program = prog.clone();
+    let steps = pre_apply(steps, &mut program, &rewritten);
+
+    let (code, executed) = lay_out(&steps[1..], &rewritten)?;
+    let first_dest = steps[0].dest;
+    if (CODE_START..CODE_START + code.len()).contains(&first_dest) {
+        return Err(Error::FirstWritesCode(first_dest));
+    }
+    program[CODE_START..CODE_START + code.len()].copy_from_slice(&code);
+
+    // The first instruction is run as well as the ones laid out
+    let optimized_steps = executed + 1;
+    if optimized_steps >= trace.executed() {
+        return Err(Error::NoSaving);
+    }
+    Ok(Optimized {
+        program,
+        original_steps: trace.executed(),
+        optimized_steps,
+        rewritten,
+    })
+}
+
+// The instructions a program executes before it stops
+#[derive(Debug, PartialEq)]
+struct Trace {
+    steps: Vec<Step>,
+    // The position just past the exit, or the end of the program if it runs off it
+    end: usize,
+    exits: bool,
+}
+
+impl Trace {
+    fn executed(&self) -> usize {
+        self.steps.len() + usize::from(self.exits)
+    }
+}
+
+// Follows the program from the start, listing the instructions it executes. Known values are
+// tracked as it goes, so instructions rewritten with known values are followed.
+fn trace(prog: &Program) -> Result<Trace, Error> {
+    let len = prog.len();
+    let mut mem: Vec<Option<i64>> = prog.iter().cloned().map(Some).collect();
+    for input in [super::NOUN_INDEX, super::VERB_INDEX].iter() {
+        if let Some(cell) = mem.get_mut(*input) {
+            *cell = None;
+        }
+    }
+
+    let mut steps = vec![];
+    let mut i = 0;
+    while i < len {
+        let op = match mem[i].map(OpCode::from) {
+            None => return Err(Error::DynamicOpCode(i)),
+            Some(OpCode::Exit) => {
+                return Ok(Trace {
+                    steps,
+                    end: i + 1,
+                    exits: true,
+                })
+            }
+            Some(OpCode::Unknown) => return Err(Error::AlwaysFails(i)),
+            Some(op) => op,
+        };
+        if i + 3 >= len {
+            return Err(Error::AlwaysFails(i));
+        }
+        let address = |cell: usize| match mem[cell] {
+            Some(addr) if addr < 0 || addr as usize >= len => Err(Error::AlwaysFails(i)),
+            Some(addr) => Ok(Some(addr as usize)),
+            None => Ok(None),
+        };
+
+        let mut args = [Operand::Const(0); 2];
+        let mut values = [None; 2];
+        for k in 0..2 {
+            match address(i + 1 + k)? {
+                Some(addr) => {
+                    args[k] = Operand::Cell(addr);
+                    values[k] = mem[addr];
+                }
+                None => args[k] = Operand::Pointer(i + 1 + k),
+            }
+        }
+        let dest = address(i + 3)?.ok_or(Error::DynamicDestination(i))?;
+
+        mem[dest] = match values {
+            [Some(a), Some(b)] if op == OpCode::Add => a.checked_add(b),
+            [Some(a), Some(b)] => a.checked_mul(b),
+            _ => None,
+        };
+        steps.push(Step {
+            at: i,
+            op,
+            args,
+            dest,
+        });
+        i += 4;
+    }
+    Ok(Trace {
+        steps,
+        end: len,
+        exits: false,
+    })
+}
+
+// Replaces reads of cells whose values are known with the values. Steps whose operands are all
+// known become copies of their result, and multiplying by zero always gives zero.
+fn fold(steps: &[Step], prog: &Program) -> Vec<Step> {
+    let mut known: Vec<Option<i64>> = prog.iter().cloned().map(Some).collect();
+    known[super::NOUN_INDEX] = None;
+    known[super::VERB_INDEX] = None;
+
+    let mut folded = vec![];
+    for step in steps {
+        let mut step = *step;
+        for arg in step.args.iter_mut() {
+            if let Operand::Cell(cell) = *arg {
+                if let Some(value) = known[cell] {
+                    *arg = Operand::Const(value);
+                }
+            }
+        }
+        let zero = step.op == OpCode::Multiply && step.args.contains(&Operand::Const(0));
+        if zero && !step.reads_pointer() {
+            step.args = [Operand::Const(0), Operand::Const(0)];
+        }
+        let value = step.value();
+        if let Some(value) = value {
+            step.op = OpCode::Add;
+            step.args = [Operand::Const(value), Operand::Const(0)];
+        }
+        known[step.dest] = value;
+        folded.push(step);
+    }
+    folded
+}
+
+// Joins steps that add or multiply by a constant onto the step before that fed them, if it did
+// the same with another constant. Constants are only joined when that can't overflow where the
+// original steps didn't, or the other way round.
+fn shorten(mut steps: Vec<Step>) -> Vec<Step> {
+    for j in 0..steps.len() {
+        let (fed, c2) = match steps[j].split_constant() {
+            Some(split) => split,
+            None => continue,
+        };
+        let i = match (0..j).rev().find(|&i| steps[i].dest == fed) {
+            Some(i) if steps[i].op == steps[j].op => i,
+            _ => continue,
+        };
+        let (from, c1) = match steps[i].split_constant() {
+            Some(split) => split,
+            None => continue,
+        };
+        // The cell the chain starts from has to hold the same value when the later step runs
+        if steps[i..j].iter().any(|step| step.dest == from) {
+            continue;
+        }
+        let joined = match steps[j].op {
+            OpCode::Add if c1.signum() * c2.signum() >= 0 => c1.checked_add(c2),
+            OpCode::Multiply if c1 != 0 && c2 != 0 => c1.checked_mul(c2),
+            _ => None,
+        };
+        if let Some(c) = joined {
+            steps[j].args = [Operand::Cell(from), Operand::Const(c)];
+        }
+    }
+    steps
+}
+
+// Drops steps whose results are never read and don't end up anywhere outside the rewritten
+// cells. Steps that read through an address from the inputs are kept, as they fault when the
+// original would, but the cells they read through may have been rewritten, so it's an error if
+// what they read is used.
+fn remove_dead(steps: &[Step], rewritten: &Range<usize>, len: usize) -> Result<Vec<Step>, Error> {
+    let mut live: Vec<bool> = (0..len).map(|cell| !rewritten.contains(&cell)).collect();
+    let mut kept = vec![];
+    for step in steps.iter().rev() {
+        let pointer = step.reads_pointer();
+        if pointer && live[step.dest] {
+            return Err(Error::UsesDynamicRead(step.at));
+        }
+        if !pointer && !live[step.dest] {
+            continue;
+        }
+        live[step.dest] = false;
+        for arg in step.args.iter() {
+            if let Operand::Cell(cell) | Operand::Pointer(cell) = *arg {
+                live[cell] = true;
+            }
+        }
+        kept.push(*step);
+    }
+    kept.reverse();
+    Ok(kept)
+}
+
+// Writes known results into the data after the program before it runs, if nothing else writes
+// to the same cell. Reads of known cells have already been folded away, so nothing can tell.
+fn pre_apply(steps: Vec<Step>, program: &mut Program, rewritten: &Range<usize>) -> Vec<Step> {
+    let mut writes: HashMap<usize, usize> = HashMap::new();
+    for step in steps.iter() {
+        *writes.entry(step.dest).or_insert(0) += 1;
+    }
+    steps
+        .into_iter()
+        .filter(|step| match step.value() {
+            Some(value) if step.dest >= rewritten.end && writes[&step.dest] == 1 => {
+                program[step.dest] = value;
+                false
+            }
+            _ => true,
+        })
+        .collect()
+}
+
+// Where the values of the optimized instructions come from. Constants and temporaries go after
+// the exit, so they're only given addresses once the number of instructions is known.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Slot {
+    Raw(i64),
+    Addr(usize),
+    Constant(usize),
+    Temp(usize),
+    // A parameter of one of the optimized instructions, for steps that read through a pointer
+    Param(usize, usize),
+}
+
+// Lays the steps out as instructions from `CODE_START`, followed by an exit and then the
+// constants and temporaries they use, returning the values and how many instructions run.
+// Results the original wrote into the rewritten cells go to temporaries instead, so they can't
+// land on the new instructions.
+fn lay_out(steps: &[Step], rewritten: &Range<usize>) -> Result<(Vec<i64>, usize), Error> {
+    let mut code: Vec<[Slot; 4]> = vec![];
+    let mut constants: BTreeMap<i64, usize> = BTreeMap::new();
+    let mut temps: HashMap<usize, usize> = HashMap::new();
+    let mut temp_count = 0;
+
+    for step in steps {
+        let mut constant = |c: i64| {
+            let next = constants.len();
+            Slot::Constant(*constants.entry(c).or_insert(next))
+        };
+        let code_of = if step.op == OpCode::Add { 1 } else { 2 };
+        let mut instruction = [Slot::Raw(code_of), Slot::Raw(0), Slot::Raw(0), Slot::Raw(0)];
+        for (k, arg) in step.args.iter().enumerate() {
+            instruction[k + 1] = match *arg {
+                Operand::Const(c) => constant(c),
+                Operand::Cell(cell) if rewritten.contains(&cell) => Slot::Temp(temps[&cell]),
+                Operand::Cell(cell) => Slot::Addr(cell),
+                // Copy the address into the parameter the instruction will read it from
+                Operand::Pointer(cell) => {
+                    let zero = constant(0);
+                    code.push([
+                        Slot::Raw(1),
+                        Slot::Temp(temps[&cell]),
+                        zero,
+                        Slot::Param(code.len() + 1, k + 1),
+                    ]);
+                    Slot::Raw(0)
+                }
+            };
+        }
+        instruction[3] = if rewritten.contains(&step.dest) {
+            temps.insert(step.dest, temp_count);
+            temp_count += 1;
+            Slot::Temp(temp_count - 1)
+        } else {
+            Slot::Addr(step.dest)
+        };
+        code.push(instruction);
+    }
+
+    let exit = CODE_START + code.len() * 4;
+    let constants_start = exit + 1;
+    let temps_start = constants_start + constants.len();
+    let len = temps_start + temp_count - CODE_START;
+    if temps_start + temp_count > rewritten.end {
+        return Err(Error::NoRoom(len));
+    }
+
+    let mut laid_out: Vec<i64> = code
+        .iter()
+        .flat_map(|instruction| instruction.iter())
+        .map(|slot| match *slot {
+            Slot::Raw(v) => v,
+            Slot::Addr(addr) => addr as i64,
+            Slot::Constant(k) => (constants_start + k) as i64,
+            Slot::Temp(k) => (temps_start + k) as i64,
+            Slot::Param(index, k) => (CODE_START + index * 4 + k) as i64,
+        })
+        .collect();
+    laid_out.push(99);
+    let mut pool = vec![0; constants.len() + temp_count];
+    for (&c, &k) in constants.iter() {
+        pool[k] = c;
+    }
+    laid_out.extend(pool);
+    Ok((laid_out, code.len() + 1))
+}
+
+// Runs both programs on random noun/verb pairs, returning the first pair where they fail
+// differently, or where the memory they leave differs outside the rewritten cells. Inputs are
+// drawn from just outside the program's bounds as well as inside them, so segmentation faults are
+// compared too.
+pub fn check_equivalence(
+    original: &Program,
+    optimized: &Optimized,
+    trials: usize,
+    seed: u64,
+) -> Result<(), (i64, i64)> {
+    let mut rng = Rng::new(seed);
+    let high = original.len() as i64 + 1;
+
+    for _ in 0..trials {
+        let noun = rng.range(-1, high);
+        let verb = rng.range(-1, high);
+        let before = set_input(original.clone(), noun, verb).and_then(run_intcode);
+        let after = set_input(optimized.program.clone(), noun, verb).and_then(run_intcode);
+        let same = match (&before, &after) {
+            (Ok(before), Ok(after)) => {
+                before.len() == after.len()
+                    && (0..before.len())
+                        .filter(|cell| !optimized.rewritten.contains(cell))
+                        .all(|cell| before[cell] == after[cell])
+            }
+            _ => before == after,
+        };
+        if !same {
+            return Err((noun, verb));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input;
+
+    fn step(at: usize, op: OpCode, args: [Operand; 2], dest: usize) -> Step {
+        Step { at, op, args, dest }
+    }
+
+    #[test]
+    fn test_trace() {
+        use Operand::*;
+        let prog = vec![1, 0, 0, 3, 2, 3, 9, 10, 99, 4, 0];
+        let first = step(0, OpCode::Add, [Pointer(1), Pointer(2)], 3);
+        let second = step(4, OpCode::Multiply, [Cell(3), Cell(9)], 10);
+        let expected = Trace {
+            steps: vec![first, second],
+            end: 9,
+            exits: true,
+        };
+        assert_eq!(Ok(expected), trace(&prog));
+        // Running off the end stops the program too
+        let expected = Trace {
+            steps: vec![first],
+            end: 4,
+            exits: false,
+        };
+        assert_eq!(Ok(expected), trace(&vec![1, 0, 0, 3]));
+
+        assert_eq!(Err(Error::AlwaysFails(4)), trace(&vec![1, 0, 0, 3, 7]));
+        assert_eq!(
+            Err(Error::AlwaysFails(4)),
+            trace(&vec![1, 0, 0, 3, 1, 9, 0, 0, 99])
+        );
+        // The first instruction writes what it read through the inputs over the second's op code
+        assert_eq!(
+            Err(Error::DynamicOpCode(4)),
+            trace(&vec![1, 0, 0, 4, 1, 0, 0, 0, 99])
+        );
+        assert_eq!(
+            Err(Error::DynamicDestination(4)),
+            trace(&vec![1, 0, 0, 7, 1, 0, 0, 0, 99])
+        );
+        // The second instruction turns the exit at 8 into a multiply, which is followed
+        let prog = vec![1, 0, 0, 3, 1, 14, 15, 8, 99, 2, 16, 0, 99, 0, 1, 1, 2];
+        let followed = trace(&prog).unwrap();
+        assert_eq!(3, followed.steps.len());
+        assert_eq!(OpCode::Multiply, followed.steps[2].op);
+        assert_eq!(13, followed.end);
+        assert_eq!(4, followed.executed());
+    }
+
+    #[test]
+    fn test_fold() {
+        use Operand::*;
+        // 3 = noun + verb, 10 = 3 * 9, 11 = 9 * 9, 12 = 11 * 3
+        let prog = vec![1, 0, 0, 3, 2, 3, 9, 10, 2, 9, 9, 11, 0, 0];
+        let steps = vec![
+            step(4, OpCode::Add, [Cell(1), Cell(2)], 3),
+            step(8, OpCode::Multiply, [Cell(3), Cell(9)], 10),
+            step(12, OpCode::Multiply, [Cell(9), Cell(9)], 11),
+            step(16, OpCode::Multiply, [Cell(11), Cell(12)], 12),
+            step(20, OpCode::Multiply, [Cell(3), Cell(13)], 13),
+        ];
+        assert_eq!(
+            vec![
+                step(4, OpCode::Add, [Cell(1), Cell(2)], 3),
+                step(8, OpCode::Multiply, [Cell(3), Const(9)], 10),
+                step(12, OpCode::Add, [Const(81), Const(0)], 11),
+                step(16, OpCode::Add, [Const(0), Const(0)], 12),
+                step(20, OpCode::Add, [Const(0), Const(0)], 13),
+            ],
+            fold(&steps, &prog)
+        );
+    }
+
+    #[test]
+    fn test_shorten() {
+        use Operand::*;
+        let steps = vec![
+            step(4, OpCode::Add, [Cell(1), Const(2)], 20),
+            step(8, OpCode::Add, [Const(3), Cell(20)], 21),
+            step(12, OpCode::Add, [Cell(21), Const(4)], 22),
+            step(16, OpCode::Multiply, [Cell(22), Const(5)], 23),
+            step(20, OpCode::Multiply, [Cell(23), Const(6)], 24),
+            // The chain's start changes before it's used
+            step(24, OpCode::Add, [Cell(2), Const(1)], 25),
+            step(28, OpCode::Add, [Cell(2), Const(1)], 2),
+            step(32, OpCode::Add, [Cell(25), Const(1)], 26),
+            // Constants of different signs could overflow differently
+            step(36, OpCode::Add, [Cell(26), Const(-1)], 27),
+        ];
+        let shortened = shorten(steps.clone());
+        assert_eq!([Cell(1), Const(5)], shortened[1].args);
+        assert_eq!([Cell(1), Const(9)], shortened[2].args);
+        assert_eq!(steps[3], shortened[3]);
+        assert_eq!([Cell(22), Const(30)], shortened[4].args);
+        assert_eq!(steps[7..], shortened[7..]);
+    }
+
+    #[test]
+    fn test_remove_dead() {
+        use Operand::*;
+        let steps = vec![
+            step(0, OpCode::Add, [Pointer(1), Pointer(2)], 3),
+            step(4, OpCode::Add, [Cell(1), Const(2)], 9),
+            step(8, OpCode::Add, [Cell(9), Const(2)], 0),
+            // Overwritten before anything reads it
+            step(12, OpCode::Add, [Cell(2), Const(2)], 17),
+            step(12, OpCode::Add, [Cell(1), Const(3)], 17),
+            // What the first instruction wrote is overwritten too
+            step(12, OpCode::Add, [Cell(2), Const(0)], 3),
+        ];
+        let kept = remove_dead(&steps, &(4..16), 18).unwrap();
+        assert_eq!(vec![steps[0], steps[1], steps[2], steps[4], steps[5]], kept);
+        // Without anything reading the cell it wrote, the second step goes too
+        assert_eq!(
+            vec![steps[0], steps[4], steps[5]],
+            remove_dead(&[steps[0], steps[1], steps[4], steps[5]], &(4..16), 18).unwrap()
+        );
+        // What the first instruction read is used
+        let reads_first = [
+            step(0, OpCode::Add, [Pointer(1), Pointer(2)], 9),
+            step(4, OpCode::Add, [Cell(9), Const(2)], 0),
+        ];
+        assert_eq!(
+            Err(Error::UsesDynamicRead(0)),
+            remove_dead(&reads_first, &(4..16), 18)
+        );
+        assert_eq!(
+            Err(Error::UsesDynamicRead(0)),
+            remove_dead(&reads_first[..1], &(4..8), 18)
+        );
+    }
+
+    #[test]
+    fn test_optimize() {
+        // 3 = noun + verb, then 0 = ((noun + 1) + 2) * 3 * 4, through intermediate results written
+        // over the instructions already run, and 33 = 5 in the data after the exit
+        let prog = vec![
+            1, 0, 0, 3, 1, 1, 2, 3, 1, 1, 29, 11, 1, 11, 30, 15, 2, 15, 31, 19, 2, 19, 32, 0, 1,
+            30, 31, 33, 99, 1, 2, 3, 4, 0,
+        ];
+        let optimized = optimize(&prog).unwrap();
+        assert_eq!(8, optimized.original_steps);
+        assert_eq!(5, optimized.optimized_steps);
+        assert_eq!(4..29, optimized.rewritten);
+        assert_eq!(prog.len(), optimized.program.len());
+        assert_eq!(5, optimized.program[33]);
+        assert_eq!(Ok(()), check_equivalence(&prog, &optimized, 1000, 1));
+
+        // Nothing to save
+        assert_eq!(Err(Error::NoSaving), optimize(&vec![1, 0, 0, 3]));
+        assert_eq!(Err(Error::NoSaving), optimize(&vec![99, 0, 0]));
+        // What the first instruction reads is the output, and could come from the rewritten cells
+        assert_eq!(
+            Err(Error::UsesDynamicRead(0)),
+            optimize(&vec![1, 0, 0, 0, 1, 5, 5, 6, 1, 9, 9, 10, 99])
+        );
+        // The first instruction writes to 9, which the original overwrites before running it, but
+        // where the optimized instructions go
+        assert_eq!(
+            Err(Error::FirstWritesCode(9)),
+            optimize(&vec![
+                1, 0, 0, 9, 1, 17, 17, 9, 1, 0, 18, 0, 1, 1, 18, 3, 99, 9, 5
+            ])
+        );
+    }
+
+    #[test]
+    fn test_optimize_pointer() {
+        // The third instruction reads through the noun plus one, which faults for the same inputs
+        // as before, but what it writes is overwritten, so the chain after it still shortens
+        let prog = vec![
+            1, 0, 0, 3, 1, 1, 29, 9, 1, 0, 0, 3, 1, 1, 29, 3, 1, 3, 29, 0, 1, 0, 29, 0, 1, 0, 29,
+            0, 99, 1,
+        ];
+        let optimized = optimize(&prog).unwrap();
+        assert!(optimized.optimized_steps < optimized.original_steps);
+        assert_eq!(Ok(()), check_equivalence(&prog, &optimized, 1000, 1));
+        let noun = prog.len() as i64 - 1;
+        assert!(set_input(prog.clone(), noun, 0)
+            .and_then(run_intcode)
+            .is_err());
+        assert!(set_input(optimized.program.clone(), noun, 0)
+            .and_then(run_intcode)
+            .is_err());
+    }
+
+    #[test]
+    fn test_check_equivalence() {
+        let prog = vec![1, 0, 0, 3, 1, 1, 2, 0, 99];
+        let same = Optimized {
+            program: prog.clone(),
+            original_steps: 2,
+            optimized_steps: 2,
+            rewritten: 4..9,
+        };
+        assert_eq!(Ok(()), check_equivalence(&prog, &same, 100, 1));
+        let other = Optimized {
+            program: vec![1, 0, 0, 3, 2, 1, 2, 0, 99],
+            ..same
+        };
+        assert!(check_equivalence(&prog, &other, 100, 1).is_err());
+    }
+
+    #[test]
+    fn test_optimize_puzzle_input() {
+        let prog = input::parse_comma_separated_ints("day2");
+        let optimized = optimize(&prog).unwrap();
+        assert_eq!(43, optimized.original_steps);
+        assert_eq!(23, optimized.optimized_steps);
+        for noun in 0..=99 {
+            for verb in 0..=99 {
+                let before = set_input(prog.clone(), noun, verb).and_then(run_intcode);
+                let after = set_input(optimized.program.clone(), noun, verb).and_then(run_intcode);
+                let (before, after) = (before.unwrap(), after.unwrap());
+                for cell in 0..prog.len() {
+                    if !optimized.rewritten.contains(&cell) {
+                        assert_eq!(before[cell], after[cell]);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,380 @@
+use super::mmio::Device;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// How each tile is drawn, as text and as a colour. Tiles without an entry are drawn as `?` in
+// magenta.
+const PALETTE: [(char, [u8; 3]); 5] = [
+    (' ', [0, 0, 0]),
+    ('#', [255, 255, 255]),
+    ('%', [200, 60, 60]),
+    ('=', [60, 120, 220]),
+    ('o', [240, 200, 40]),
+];
+const UNKNOWN_TILE: (char, [u8; 3]) = ('?', [255, 0, 255]);
+
+// Each tile is this many pixels square in image output
+const TILE_PIXELS: usize = 4;
+
+// The most tiles a screen can be drawn with, counting the blank ones between those set
+const MAX_TILES: u128 = 1 << 20;
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    Empty,
+    TooLarge(u128, u128),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Empty => write!(f, "Nothing has been drawn on the screen"),
+            Error::TooLarge(width, height) => write!(
+                f,
+                "The screen is {}x{} tiles, more than the {} that can be drawn",
+                width, height, MAX_TILES
+            ),
+        }
+    }
+}
+
+// The smallest and largest x and y drawn on
+type Bounds = ((i64, i64), (i64, i64));
+
+// A 2D grid of tiles that grows to fit whatever is drawn on it, including negative coordinates.
+#[derive(Debug, Default)]
+pub struct Screen {
+    tiles: HashMap<(i64, i64), i64>,
+}
+
+impl Screen {
+    pub fn set(&mut self, x: i64, y: i64, tile: i64) {
+        self.tiles.insert((x, y), tile);
+    }
+
+    // The bounds of what has been drawn, as long as there's something and it isn't too spread out
+    // to draw
+    fn bounds(&self) -> Result<Bounds, Error> {
+        let mut coords = self.tiles.keys();
+        let &(x, y) = coords.next().ok_or(Error::Empty)?;
+        let ((min_x, min_y), (max_x, max_y)) = coords
+            .fold(((x, y), (x, y)), |(min, max), &(x, y)| {
+                ((min.0.min(x), min.1.min(y)), (max.0.max(x), max.1.max(y)))
+            });
+
+        let width = (max_x as i128 - min_x as i128 + 1) as u128;
+        let height = (max_y as i128 - min_y as i128 + 1) as u128;
+        match width.checked_mul(height) {
+            Some(tiles) if tiles <= MAX_TILES => Ok(((min_x, min_y), (max_x, max_y))),
+            _ => Err(Error::TooLarge(width, height)),
+        }
+    }
+
+    fn style(&self, x: i64, y: i64) -> (char, [u8; 3]) {
+        let tile = self.tiles.get(&(x, y)).cloned().unwrap_or(0);
+        if tile >= 0 && (tile as usize) < PALETTE.len() {
+            PALETTE[tile as usize]
+        } else {
+            UNKNOWN_TILE
+        }
+    }
+
+    // Draws the screen as text, which is empty if nothing has been drawn
+    pub fn render(&self) -> Result<String, Error> {
+        let ((min_x, min_y), (max_x, max_y)) = match self.bounds() {
+            Ok(bounds) => bounds,
+            Err(Error::Empty) => return Ok(String::new()),
+            Err(why) => return Err(why),
+        };
+
+        let mut text = String::new();
+        for y in min_y..=max_y {
+            let row: String = (min_x..=max_x).map(|x| self.style(x, y).0).collect();
+            text.push_str(row.trim_end());
+            text.push('\n');
+        }
+        Ok(text)
+    }
+
+    // The image as rows of RGB bytes, with its width and height in pixels
+    fn pixels(&self) -> Result<(usize, usize, Vec<Vec<u8>>), Error> {
+        let ((min_x, min_y), (max_x, max_y)) = self.bounds()?;
+        let width = (max_x - min_x + 1) as usize * TILE_PIXELS;
+
+        let mut rows = vec![];
+        for y in min_y..=max_y {
+            let row: Vec<u8> = (min_x..=max_x)
+                .flat_map(|x| {
+                    let colour = self.style(x, y).1;
+                    std::iter::repeat_n(colour, TILE_PIXELS).flatten()
+                })
+                .collect();
+            for _ in 0..TILE_PIXELS {
+                rows.push(row.clone());
+            }
+        }
+        Ok((width, rows.len(), rows))
+    }
+
+    pub fn to_ppm(&self) -> Result<Vec<u8>, Error> {
+        let (width, height, rows) = self.pixels()?;
+        let mut ppm = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+        for row in rows {
+            ppm.extend(row);
+        }
+        Ok(ppm)
+    }
+
+    // PNG data is zlib compressed, but stored blocks need no compressor, so the image is written
+    // uncompressed.
+    pub fn to_png(&self) -> Result<Vec<u8>, Error> {
+        let (width, height, rows) = self.pixels()?;
+
+        let mut header = vec![];
+        header.extend(&(width as u32).to_be_bytes());
+        header.extend(&(height as u32).to_be_bytes());
+        // 8 bits per channel, RGB, default compression, filtering and no interlacing
+        header.extend(&[8, 2, 0, 0, 0]);
+
+        let mut raw = vec![];
+        for row in rows {
+            // Each row starts with its filter type, which is none
+            raw.push(0);
+            raw.extend(row);
+        }
+
+        let mut png = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+        png_chunk(&mut png, b"IHDR", &header);
+        png_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+        png_chunk(&mut png, b"IEND", &[]);
+        Ok(png)
+    }
+
+    // Writes the screen as a PNG or PPM image, chosen by the file extension
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let image = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("png") => self.to_png(),
+            Some("ppm") => self.to_ppm(),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "images must be saved as .png or .ppm",
+                ))
+            }
+        };
+        match image {
+            Ok(image) => fs::write(path, image),
+            Err(why) => Err(io::Error::new(io::ErrorKind::InvalidInput, why.to_string())),
+        }
+    }
+}
+
+fn png_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend(kind);
+    png.extend(data);
+    let crc = crc32(&png[start..]);
+    png.extend(&crc.to_be_bytes());
+}
+
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    // No preset dictionary, fastest compression level
+    let mut zlib = vec![0x78, 0x01];
+    let mut blocks = data.chunks(0xffff).peekable();
+    if blocks.peek().is_none() {
+        zlib.extend(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        zlib.push(if last { 1 } else { 0 });
+        let len = block.len() as u16;
+        zlib.extend(&len.to_le_bytes());
+        zlib.extend(&(!len).to_le_bytes());
+        zlib.extend(block);
+    }
+    zlib.extend(&adler32(data).to_be_bytes());
+    zlib
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+// Collects `x, y, tile` triples written to a single address and draws them on a screen.
+#[derive(Default)]
+pub struct TileSink {
+    pending: Vec<i64>,
+    screen: Screen,
+}
+
+impl TileSink {
+    pub fn screen(&self) -> &Screen {
+        &self.screen
+    }
+}
+
+impl Device for TileSink {
+    fn read(&mut self, _: usize) -> i64 {
+        0
+    }
+
+    fn write(&mut self, _: usize, value: i64) {
+        self.pending.push(value);
+        if let [x, y, tile] = self.pending[..] {
+            self.screen.set(x, y, tile);
+            self.pending.clear();
+        }
+    }
+}
+
+// Decodes values written to a single address as ASCII text. Anything outside the ASCII range is
+// kept separately, since programs often report a final answer that way.
+#[derive(Default)]
+pub struct AsciiSink {
+    text: String,
+    other: Vec<i64>,
+}
+
+impl AsciiSink {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn other(&self) -> &[i64] {
+        &self.other
+    }
+}
+
+impl Device for AsciiSink {
+    fn read(&mut self, _: usize) -> i64 {
+        0
+    }
+
+    fn write(&mut self, _: usize, value: i64) {
+        if (0..128).contains(&value) {
+            self.text.push(value as u8 as char);
+        } else {
+            self.other.push(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let mut screen = Screen::default();
+        assert_eq!(Ok(String::new()), screen.render());
+
+        screen.set(0, 0, 1);
+        screen.set(2, 0, 1);
+        screen.set(-1, 1, 4);
+        screen.set(1, 2, 7);
+        assert_eq!(Ok(" # #\no\n  ?\n".to_string()), screen.render());
+    }
+
+    #[test]
+    fn test_bounds() {
+        let mut screen = Screen::default();
+        assert_eq!(Err(Error::Empty), screen.to_png());
+        assert_eq!(Err(Error::Empty), screen.to_ppm());
+
+        screen.set(0, 0, 1);
+        screen.set(1023, 1023, 1);
+        assert!(screen.bounds().is_ok());
+        screen.set(1024, 0, 1);
+        assert_eq!(Err(Error::TooLarge(1025, 1024)), screen.render());
+
+        // Tiles at the ends of the coordinates mustn't overflow working out the size
+        let mut screen = Screen::default();
+        screen.set(i64::MIN, i64::MIN, 1);
+        screen.set(i64::MAX, i64::MAX, 1);
+        let side = 1 << 64;
+        assert_eq!(Err(Error::TooLarge(side, side)), screen.render());
+        assert!(screen.to_png().is_err());
+    }
+
+    #[test]
+    fn test_tile_sink() {
+        let mut sink = TileSink::default();
+        for &v in [1, 0, 2, 0, 0].iter() {
+            sink.write(0, v);
+        }
+        assert_eq!(Ok("%\n".to_string()), sink.screen().render());
+        sink.write(0, 3);
+        assert_eq!(Ok("=%\n".to_string()), sink.screen().render());
+    }
+
+    #[test]
+    fn test_ascii_sink() {
+        let mut sink = AsciiSink::default();
+        for &v in [72, 105, 10, 12345, -1].iter() {
+            sink.write(0, v);
+        }
+        assert_eq!("Hi\n", sink.text());
+        assert_eq!(&[12345, -1], sink.other());
+    }
+
+    #[test]
+    fn test_to_ppm() {
+        let mut screen = Screen::default();
+        screen.set(0, 0, 1);
+        let ppm = screen.to_ppm().unwrap();
+        let header = b"P6\n4 4\n255\n";
+        assert_eq!(&header[..], &ppm[..header.len()]);
+        assert_eq!(header.len() + 4 * 4 * 3, ppm.len());
+        assert!(ppm[header.len()..].iter().all(|&b| b == 255));
+    }
+
+    #[test]
+    fn test_to_png() {
+        let mut screen = Screen::default();
+        screen.set(0, 0, 0);
+        screen.set(1, 0, 1);
+        let png = screen.to_png().unwrap();
+        assert_eq!(b"\x89PNG\r\n\x1a\n", &png[..8]);
+        assert_eq!(b"IHDR", &png[12..16]);
+        assert_eq!([0, 0, 0, 8, 0, 0, 0, 4], png[16..24]);
+        // Every PNG ends with the same empty IEND chunk
+        assert_eq!(
+            [0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82],
+            png[png.len() - 12..]
+        );
+    }
+
+    #[test]
+    fn test_checksums() {
+        assert_eq!(0xcbf4_3926, crc32(b"123456789"));
+        assert_eq!(0x11e6_0398, adler32(b"Wikipedia"));
+        assert_eq!(
+            vec![0x78, 0x01, 1, 2, 0, 0xfd, 0xff, b'h', b'i', 0x01, 0x3b, 0x00, 0xd2],
+            zlib_stored(b"hi")
+        );
+    }
+}
@@ -0,0 +1,360 @@
+use super::{run_intcode, set_input, Program};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+// A conformance case is a small text file made of `key: value` lines. Blank lines and lines
+// starting with `#` are ignored.
+//
+//     program: 1,9,10,3,2,3,11,0,99,30,40,50
+//     inputs: 12,2
+//     output: 3500
+//     memory: 3500,9,10,70,2,3,11,0,99,30,40,50
+//     error: SegFault
+//
+// `program` is required. `inputs` sets the noun and verb before running, as `execute` does.
+// At least one of `output` (the value left in position 0), `memory` (the whole final memory) or
+// `error` (the `Debug` form of the expected error) must be given.
+const CASE_EXTENSION: &str = "case";
+
+#[derive(Debug, PartialEq)]
+enum Error {
+    MissingProgram,
+    NoExpectation,
+    BadLine(usize),
+    UnknownKey(usize, String),
+    DuplicateKey(usize, String),
+    BadNumber(usize),
+    BadInputs(usize),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::MissingProgram => write!(f, "No program given"),
+            Error::NoExpectation => write!(f, "No output, memory or error expected"),
+            Error::BadLine(line) => write!(f, "Line {} is not a `key: value` pair", line),
+            Error::UnknownKey(line, key) => write!(f, "Unknown key '{}' on line {}", key, line),
+            Error::DuplicateKey(line, key) => write!(f, "Key '{}' repeated on line {}", key, line),
+            Error::BadNumber(line) => write!(f, "Line {} contains an invalid number", line),
+            Error::BadInputs(line) => write!(f, "Line {} must give exactly two inputs", line),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Case {
+    program: Program,
+    inputs: Option<(i64, i64)>,
+    output: Option<i64>,
+    memory: Option<Program>,
+    error: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Pass,
+    Fail(String),
+    Invalid(String),
+}
+
+// Runs every case file in `dir` and prints a table of the results. Returns whether every case
+// passed.
+pub fn run(dir: &str) -> bool {
+    let paths = match case_paths(Path::new(dir)) {
+        Ok(paths) => paths,
+        Err(why) => {
+            println!("Couldn't read test cases from {}: {}", dir, why);
+            return false;
+        }
+    };
+    if paths.is_empty() {
+        println!("No .{} files found in {}", CASE_EXTENSION, dir);
+        return false;
+    }
+
+    let results: Vec<(String, Outcome)> = paths
+        .iter()
+        .map(|path| {
+            let name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let outcome = match fs::read_to_string(path) {
+                Ok(text) => match parse_case(&text) {
+                    Ok(case) => run_case(&case),
+                    Err(why) => Outcome::Invalid(why.to_string()),
+                },
+                Err(why) => Outcome::Invalid(why.to_string()),
+            };
+            (name, outcome)
+        })
+        .collect();
+
+    print!("{}", format_table(&results));
+
+    results.iter().all(|(_, outcome)| *outcome == Outcome::Pass)
+}
+
+fn case_paths(dir: &Path) -> std::io::Result<Vec<std::path::PathBuf>> {
+    let mut paths = fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    paths
+        .retain(|path| path.is_file() && path.extension().is_some_and(|ext| ext == CASE_EXTENSION));
+    paths.sort();
+    Ok(paths)
+}
+
+fn parse_case(text: &str) -> Result<Case, Error> {
+    let mut case = Case {
+        program: vec![],
+        inputs: None,
+        output: None,
+        memory: None,
+        error: None,
+    };
+    let mut seen_program = false;
+
+    for (i, line) in text.lines().enumerate() {
+        let line_no = i + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut parts = line.splitn(2, ':');
+        let key = parts.next().unwrap().trim();
+        let value = match parts.next() {
+            Some(value) => value.trim(),
+            None => return Err(Error::BadLine(line_no)),
+        };
+
+        let duplicate = match key {
+            "program" => {
+                let dup = seen_program;
+                case.program = parse_ints(value, line_no)?;
+                seen_program = true;
+                dup
+            }
+            "inputs" => match parse_ints(value, line_no)?.as_slice() {
+                [noun, verb] => case.inputs.replace((*noun, *verb)).is_some(),
+                _ => return Err(Error::BadInputs(line_no)),
+            },
+            "output" => {
+                let output = value.parse().map_err(|_| Error::BadNumber(line_no))?;
+                case.output.replace(output).is_some()
+            }
+            "memory" => case.memory.replace(parse_ints(value, line_no)?).is_some(),
+            "error" => case.error.replace(value.to_string()).is_some(),
+            _ => return Err(Error::UnknownKey(line_no, key.to_string())),
+        };
+        if duplicate {
+            return Err(Error::DuplicateKey(line_no, key.to_string()));
+        }
+    }
+
+    if !seen_program {
+        return Err(Error::MissingProgram);
+    }
+    if case.output.is_none() && case.memory.is_none() && case.error.is_none() {
+        return Err(Error::NoExpectation);
+    }
+
+    Ok(case)
+}
+
+fn parse_ints(value: &str, line_no: usize) -> Result<Vec<i64>, Error> {
+    if value.is_empty() {
+        return Ok(vec![]);
+    }
+    value
+        .split(',')
+        .map(|n| {
+            n.trim()
+                .parse::<i64>()
+                .map_err(|_| Error::BadNumber(line_no))
+        })
+        .collect()
+}
+
+fn run_case(case: &Case) -> Outcome {
+    let result = match case.inputs {
+        Some((noun, verb)) => set_input(case.program.clone(), noun, verb).and_then(run_intcode),
+        None => run_intcode(case.program.clone()),
+    };
+
+    match (result, &case.error) {
+        (Err(e), Some(expected)) => {
+            let actual = format!("{:?}", e);
+            if actual == *expected {
+                Outcome::Pass
+            } else {
+                Outcome::Fail(format!("expected error {}, got error {}", expected, actual))
+            }
+        }
+        (Err(e), None) => Outcome::Fail(format!("unexpected error {:?}", e)),
+        (Ok(mem), Some(expected)) => {
+            Outcome::Fail(format!("expected error {}, got {}", expected, join(&mem)))
+        }
+        (Ok(mem), None) => {
+            if let Some(expected) = case.output {
+                match mem.first() {
+                    Some(&actual) if actual == expected => (),
+                    actual => {
+                        return Outcome::Fail(format!(
+                            "expected output {}, got {}",
+                            expected,
+                            actual.map_or("nothing".to_string(), i64::to_string)
+                        ))
+                    }
+                }
+            }
+            if let Some(expected) = &case.memory {
+                if mem != *expected {
+                    return Outcome::Fail(format!(
+                        "expected memory {}, got {}",
+                        join(expected),
+                        join(&mem)
+                    ));
+                }
+            }
+            Outcome::Pass
+        }
+    }
+}
+
+fn join(mem: &[i64]) -> String {
+    mem.iter().map(i64::to_string).collect::<Vec<_>>().join(",")
+}
+
+fn format_table(results: &[(String, Outcome)]) -> String {
+    let width = results
+        .iter()
+        .map(|(name, _)| name.len())
+        .chain(std::iter::once("CASE".len()))
+        .max()
+        .unwrap();
+
+    let mut table = format!(
+        "{:<width$}  {:<7}  DETAIL\n",
+        "CASE",
+        "RESULT",
+        width = width
+    );
+    let mut passed = 0;
+    for (name, outcome) in results {
+        let (result, detail) = match outcome {
+            Outcome::Pass => {
+                passed += 1;
+                ("pass", "")
+            }
+            Outcome::Fail(detail) => ("FAIL", detail.as_str()),
+            Outcome::Invalid(detail) => ("INVALID", detail.as_str()),
+        };
+        let row = format!("{:<width$}  {:<7}  {}", name, result, detail, width = width);
+        table.push_str(row.trim_end());
+        table.push('\n');
+    }
+    table.push_str(&format!(
+        "{} passed, {} failed\n",
+        passed,
+        results.len() - passed
+    ));
+
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_case() {
+        assert_eq!(Err(Error::MissingProgram), parse_case("output: 1"));
+        assert_eq!(Err(Error::NoExpectation), parse_case("program: 99"));
+        assert_eq!(Err(Error::BadLine(2)), parse_case("program: 99\noutput 99"));
+        assert_eq!(
+            Err(Error::UnknownKey(1, "prog".to_string())),
+            parse_case("prog: 99")
+        );
+        assert_eq!(
+            Err(Error::DuplicateKey(3, "output".to_string())),
+            parse_case("program: 99\noutput: 99\noutput: 98")
+        );
+        assert_eq!(Err(Error::BadNumber(1)), parse_case("program: 1,x,3"));
+        assert_eq!(
+            Err(Error::BadInputs(2)),
+            parse_case("program: 99\ninputs: 1\noutput: 99")
+        );
+        assert_eq!(
+            Ok(Case {
+                program: vec![1, 0, 0, 0, 99],
+                inputs: Some((12, 2)),
+                output: Some(2),
+                memory: Some(vec![2, 0, 0, 0, 99]),
+                error: None,
+            }),
+            parse_case(
+                "# Adds position 0 to itself\n\nprogram: 1,0,0,0,99\ninputs: 12, 2\noutput: 2\nmemory: 2,0,0,0,99\n"
+            )
+        );
+        assert_eq!(
+            Ok(Case {
+                program: vec![],
+                inputs: None,
+                output: None,
+                memory: None,
+                error: Some("ProgramTooShort".to_string()),
+            }),
+            parse_case("program:\nerror: ProgramTooShort")
+        );
+    }
+
+    #[test]
+    fn test_run_case() {
+        let case = parse_case("program: 1,0,0,0,99\noutput: 2\nmemory: 2,0,0,0,99").unwrap();
+        assert_eq!(Outcome::Pass, run_case(&case));
+
+        let case = parse_case("program: 1,0,0,0,99\noutput: 3").unwrap();
+        assert_eq!(
+            Outcome::Fail("expected output 3, got 2".to_string()),
+            run_case(&case)
+        );
+
+        let case = parse_case("program: 1,0,0,5,99\nerror: SegFault").unwrap();
+        assert_eq!(Outcome::Pass, run_case(&case));
+
+        let case = parse_case("program: 1,1,1\nerror: NotEnoughParams(Add)").unwrap();
+        assert_eq!(Outcome::Pass, run_case(&case));
+
+        let case = parse_case("program: 1,0,0,0,99\nerror: SegFault").unwrap();
+        assert_eq!(
+            Outcome::Fail("expected error SegFault, got 2,0,0,0,99".to_string()),
+            run_case(&case)
+        );
+
+        let case = parse_case("program: 1,0,0,0,99\ninputs: 4,4\noutput: 198").unwrap();
+        assert_eq!(Outcome::Pass, run_case(&case));
+    }
+
+    #[test]
+    fn test_format_table() {
+        let results = vec![
+            ("add".to_string(), Outcome::Pass),
+            (
+                "segfault".to_string(),
+                Outcome::Fail("unexpected error SegFault".to_string()),
+            ),
+        ];
+        assert_eq!(
+            "CASE      RESULT   DETAIL\nadd       pass\nsegfault  FAIL     unexpected error SegFault\n1 passed, 1 failed\n",
+            format_table(&results)
+        );
+    }
+
+    #[test]
+    fn test_corpus() {
+        assert!(run("./input/day2-cases"));
+    }
+}
@@ -0,0 +1,284 @@
+use super::{parse_wires, segment_crossing, vertices, Coord, Crossing, Wire};
+use std::collections::BTreeMap;
+
+// A stretch of wire that starts and ends at the same point. The area is measured in the x-y
+// plane, and where the loop crosses itself each part it's split into counts, whichever way it
+// winds.
+#[derive(Debug, PartialEq)]
+struct Loop {
+    at: Coord,
+    from: i64,
+    to: i64,
+    area: f64,
+}
+
+#[derive(Debug, PartialEq)]
+struct Analysis {
+    length: i64,
+    bounds: (Coord, Coord),
+    crossings: BTreeMap<Coord, Vec<i64>>,
+    loops: Vec<Loop>,
+}
+
+// Describes each wire on its own: how long it is, the box it fits in, where it crosses itself and
+// the loops that makes.
+pub fn report(input: &str) {
+    let wires = match parse_wires(input) {
+        None => return,
+        Some(wires) => wires,
+    };
+
+    for (i, wire) in wires.iter().enumerate() {
+        let analysis = analyse(wire);
+        println!(
+            "Wire {}: {} steps long, fitting between {:?} and {:?}",
+            i + 1,
+            analysis.length,
+            analysis.bounds.0,
+            analysis.bounds.1
+        );
+        println!("  Crosses itself at {} points", analysis.crossings.len());
+        for (coord, steps) in analysis.crossings.iter() {
+            let steps: Vec<String> = steps.iter().map(|s| s.to_string()).collect();
+            println!("    {:?} at steps {}", coord, steps.join(", "));
+        }
+        println!("  Makes {} loops", analysis.loops.len());
+        for found in analysis.loops.iter() {
+            println!(
+                "    From {:?} between steps {} and {}, enclosing an area of {}",
+                found.at, found.from, found.to, found.area
+            );
+        }
+    }
+}
+
+fn analyse(wire: &Wire) -> Analysis {
+    let corners = vertices(wire);
+    let (mut min, mut max) = (corners[0], corners[0]);
+    for &(x, y, z) in corners.iter() {
+        min = (min.0.min(x), min.1.min(y), min.2.min(z));
+        max = (max.0.max(x), max.1.max(y), max.2.max(z));
+    }
+    let length = wire.last().map_or(0, |last| last.steps + last.length());
+
+    let crossings = self_crossings(wire);
+    let loops = crossings
+        .iter()
+        .flat_map(|(&at, steps)| {
+            steps.windows(2).map(move |pair| Loop {
+                at,
+                from: pair[0],
+                to: pair[1],
+                area: loop_area(wire, at, pair[0], pair[1]),
+            })
+        })
+        .collect();
+
+    Analysis {
+        length,
+        bounds: (min, max),
+        crossings,
+        loops,
+    }
+}
+
+// Every point the wire passes through more than once, and the steps taken each time it gets
+// there. Each pair of segments is checked, as a wire crossing itself can't be swept like two
+// separate wires.
+fn self_crossings(wire: &Wire) -> BTreeMap<Coord, Vec<i64>> {
+    let mut visits: BTreeMap<Coord, Vec<i64>> = BTreeMap::new();
+    for (i, segment1) in wire.iter().enumerate() {
+        for segment2 in wire[i + 1..].iter() {
+            let (from, to) = match segment_crossing(segment1, segment2) {
+                Some(run) => run,
+                None => continue,
+            };
+            let run = Crossing {
+                from,
+                to,
+                segment1: *segment1,
+                segment2: *segment2,
+            };
+            for coord in run.points() {
+                let (first, second) = (segment1.steps_to(coord), segment2.steps_to(coord));
+                // Neighbouring segments share the corner between them
+                if first == second {
+                    continue;
+                }
+                let steps = visits.entry(coord).or_default();
+                steps.push(first);
+                steps.push(second);
+            }
+        }
+    }
+
+    for steps in visits.values_mut() {
+        steps.sort_unstable();
+        steps.dedup();
+    }
+    visits
+}
+
+// The area enclosed by the wire between two visits to `at`. Loops where the wire only doubles
+// back on itself enclose nothing.
+fn loop_area(wire: &Wire, at: Coord, from: i64, to: i64) -> f64 {
+    let mut polygon = vec![at];
+    polygon.extend(
+        wire.iter()
+            .map(|segment| (segment.steps + segment.length(), segment.end))
+            .filter(|&(steps, _)| steps > from && steps < to)
+            .map(|(_, corner)| corner),
+    );
+    split_area(
+        polygon
+            .into_iter()
+            .map(|(x, y, _)| (x as f64, y as f64))
+            .collect(),
+    )
+}
+
+type Point = (f64, f64);
+
+// The area of a closed polygon, with the last corner joined back to the first. Where two of its
+// sides meet, the polygon is split in two there, each going round one side of the meeting point,
+// and the areas of the two are added, so parts that wind opposite ways don't cancel each other
+// out. Each part has fewer corners than the whole, so the splitting ends with simple polygons,
+// measured by the shoelace formula.
+fn split_area(mut polygon: Vec<Point>) -> f64 {
+    polygon.dedup();
+    while polygon.len() > 1 && polygon.first() == polygon.last() {
+        polygon.pop();
+    }
+    let n = polygon.len();
+    let side = |i: usize| (polygon[i], polygon[(i + 1) % n]);
+
+    for i in 0..n {
+        // Neighbouring sides always meet at the corner between them
+        for j in (i + 2)..(if i == 0 { n - 1 } else { n }) {
+            if let Some(meet) = side_crossing(side(i), side(j)) {
+                let mut inner = vec![meet];
+                inner.extend_from_slice(&polygon[i + 1..=j]);
+                let mut outer = polygon[..=i].to_vec();
+                outer.push(meet);
+                outer.extend_from_slice(&polygon[j + 1..]);
+                return split_area(inner) + split_area(outer);
+            }
+        }
+    }
+
+    let twice_area: f64 = (0..n)
+        .map(|i| {
+            let ((x1, y1), (x2, y2)) = side(i);
+            x1 * y2 - x2 * y1
+        })
+        .sum();
+    twice_area.abs() / 2.0
+}
+
+// A point where two sides of a polygon meet, if they do. Sides along the same line can share a
+// stretch, and then the start of it is taken.
+fn side_crossing((a, b): (Point, Point), (c, d): (Point, Point)) -> Option<Point> {
+    let cross = |u: Point, v: Point| u.0 * v.1 - u.1 * v.0;
+    let (r, s, between) = (
+        (b.0 - a.0, b.1 - a.1),
+        (d.0 - c.0, d.1 - c.1),
+        (c.0 - a.0, c.1 - a.1),
+    );
+    let at = |t: f64| (a.0 + t * r.0, a.1 + t * r.1);
+
+    let denominator = cross(r, s);
+    if denominator == 0.0 {
+        if cross(between, r) != 0.0 {
+            return None;
+        }
+        let length = r.0 * r.0 + r.1 * r.1;
+        let along = |p: Point| ((p.0 - a.0) * r.0 + (p.1 - a.1) * r.1) / length;
+        let (t1, t2) = (along(c), along(d));
+        let (lo, hi) = (t1.min(t2).max(0.0), t1.max(t2).min(1.0));
+        return if lo <= hi { Some(at(lo)) } else { None };
+    }
+
+    let t = cross(between, s) / denominator;
+    let u = cross(between, r) / denominator;
+    if (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u) {
+        Some(at(t))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day3::get_wire;
+
+    #[test]
+    fn test_analyse() {
+        let analysis = analyse(&get_wire("R4,U4,L2,D6").unwrap());
+        assert_eq!(16, analysis.length);
+        assert_eq!(((0, -2, 0), (4, 4, 0)), analysis.bounds);
+        assert_eq!(
+            vec![(&(2, 0, 0), &vec![2, 14])],
+            analysis.crossings.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![Loop {
+                at: (2, 0, 0),
+                from: 2,
+                to: 14,
+                area: 8.0
+            }],
+            analysis.loops
+        );
+    }
+
+    #[test]
+    fn test_loops() {
+        // Back to the start
+        let analysis = analyse(&get_wire("U2,R2,D2,L2").unwrap());
+        assert_eq!(1, analysis.loops.len());
+        assert_eq!(4.0, analysis.loops[0].area);
+
+        // Doubling back makes loops that enclose nothing
+        let analysis = analyse(&get_wire("R5,L2").unwrap());
+        assert_eq!(
+            vec![&(3, 0, 0), &(4, 0, 0)],
+            analysis.crossings.keys().collect::<Vec<_>>()
+        );
+        let areas: Vec<f64> = analysis.loops.iter().map(|found| found.area).collect();
+        assert_eq!(vec![0.0, 0.0], areas);
+
+        // A figure of eight crosses itself in the middle and ends at the start. The halves of the
+        // loop through the start wind in opposite directions, but both count.
+        let analysis = analyse(&get_wire("NE2,D2,NW2,D2").unwrap());
+        assert_eq!(
+            vec![(&(0, 0, 0), &vec![0, 8]), (&(1, 1, 0), &vec![1, 5])],
+            analysis.crossings.iter().collect::<Vec<_>>()
+        );
+        let areas: Vec<f64> = analysis.loops.iter().map(|found| found.area).collect();
+        assert_eq!(vec![2.0, 1.0], areas);
+
+        // The same goes for halves that cross between grid points
+        let analysis = analyse(&get_wire("R1,NW1,R1,SW1").unwrap());
+        let areas: Vec<f64> = analysis.loops.iter().map(|found| found.area).collect();
+        assert_eq!(vec![0.5], areas);
+        let bowtie = vec![(0.0, 0.0), (2.0, 0.0), (0.0, 2.0), (2.0, 2.0)];
+        assert_eq!(2.0, split_area(bowtie));
+        // and for squares that wind opposite ways, only touching at a corner
+        let squares = vec![
+            (0.0, 0.0),
+            (1.0, 0.0),
+            (1.0, 1.0),
+            (0.0, 1.0),
+            (0.0, 0.0),
+            (0.0, -1.0),
+            (-1.0, -1.0),
+            (-1.0, 0.0),
+        ];
+        assert_eq!(2.0, split_area(squares));
+
+        let analysis = analyse(&get_wire("R1").unwrap());
+        assert!(analysis.crossings.is_empty());
+        assert_eq!(0, analyse(&Wire::new()).length);
+    }
+}
@@ -0,0 +1,327 @@
+use super::index::steps_inside;
+use super::{advance, cross, get_paths, shared_runs, vertices, Coord, Wire};
+
+// What has been drawn in a character cell. Once the grid is downsampled a cell covers a block of
+// points, and shows the most important thing in any of them.
+const HORIZONTAL: u8 = 1;
+const VERTICAL: u8 = 2;
+const CORNER: u8 = 4;
+const CROSSING: u8 = 8;
+const ORIGIN: u8 = 16;
+const RISING: u8 = 32;
+const FALLING: u8 = 64;
+const OBLIQUE: u8 = 128;
+const LINES: u8 = HORIZONTAL | VERTICAL | RISING | FALLING | OBLIQUE;
+
+// Drawings bigger than this are refused rather than flooding the terminal
+const MAX_CELLS: i128 = 10_000_000;
+
+// The part of the grid to draw, including both corners. Every layer is drawn on top of each
+// other.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Viewport {
+    min: (i64, i64),
+    max: (i64, i64),
+}
+
+impl Viewport {
+    fn contains(&self, (x, y, _): Coord) -> bool {
+        x >= self.min.0 && x <= self.max.0 && y >= self.min.1 && y <= self.max.1
+    }
+
+    // The width and height of the drawing when each character covers `scale` by `scale` points.
+    // A viewport can span the whole number line, so they're worked out wider.
+    fn cells(&self, scale: i64) -> (i128, i128) {
+        let cells = |min: i64, max: i64| (max as i128 - min as i128) / scale as i128 + 1;
+        (cells(self.min.0, self.max.0), cells(self.min.1, self.max.1))
+    }
+}
+
+// Draws the wires the way the puzzle examples do. `viewport` crops the drawing to `X1,Y1,X2,Y2`,
+// `scale` has each character cover that many points square, and `follow` draws only that wire
+// (counting from 1), with the places other wires cross it still marked.
+pub fn render(input: &str, viewport: Option<&str>, scale: i64, follow: Option<usize>) {
+    let wires = match get_paths(input) {
+        None => return,
+        Some(wires) => wires,
+    };
+
+    if scale < 1 {
+        println!("The scale must be at least 1");
+        return;
+    }
+    let follow = match follow {
+        Some(wire) if wire < 1 || wire > wires.len() => {
+            println!("There is no wire {}", wire);
+            return;
+        }
+        Some(wire) => Some(wire - 1),
+        None => None,
+    };
+    let viewport = match viewport {
+        Some(spec) => match parse_viewport(spec) {
+            Some(viewport) => viewport,
+            None => {
+                println!("The viewport must be given as X1,Y1,X2,Y2");
+                return;
+            }
+        },
+        None => bounds(&wires, follow),
+    };
+
+    let (width, height) = viewport.cells(scale);
+    if width
+        .checked_mul(height)
+        .is_none_or(|cells| cells > MAX_CELLS)
+    {
+        println!(
+            "The drawing would be {} by {} characters, crop it with --viewport or shrink it with --scale",
+            width, height
+        );
+        return;
+    }
+    print!("{}", draw(&wires, follow, viewport, scale));
+}
+
+fn parse_viewport(spec: &str) -> Option<Viewport> {
+    let values: Result<Vec<i64>, _> = spec.split(',').map(|v| v.trim().parse()).collect();
+    match values.ok()?[..] {
+        [x1, y1, x2, y2] => Some(Viewport {
+            min: (x1.min(x2), y1.min(y2)),
+            max: (x1.max(x2), y1.max(y2)),
+        }),
+        _ => None,
+    }
+}
+
+// Everything the drawn wires cover, and the origin, with a point of space around it like the
+// puzzle examples
+fn bounds(wires: &[Wire], follow: Option<usize>) -> Viewport {
+    let (mut min, mut max) = ((0, 0), (0, 0));
+    for (_, wire) in drawn(wires, follow) {
+        for (x, y, _) in vertices(wire) {
+            min = (min.0.min(x), min.1.min(y));
+            max = (max.0.max(x), max.1.max(y));
+        }
+    }
+    Viewport {
+        min: (min.0.saturating_sub(1), min.1.saturating_sub(1)),
+        max: (max.0.saturating_add(1), max.1.saturating_add(1)),
+    }
+}
+
+fn drawn(wires: &[Wire], follow: Option<usize>) -> impl Iterator<Item = (usize, &Wire)> {
+    wires
+        .iter()
+        .enumerate()
+        .filter(move |&(i, _)| follow.is_none_or(|f| f == i))
+}
+
+fn draw(wires: &[Wire], follow: Option<usize>, viewport: Viewport, scale: i64) -> String {
+    let (width, height) = viewport.cells(scale);
+    let mut cells = vec![vec![0u8; width as usize]; height as usize];
+    // The steps of a run that land in the viewport, in any layer, so long runs aren't walked
+    // outside it
+    let inside = |start: Coord, step: Coord, length: i64| {
+        let min = (viewport.min.0, viewport.min.1, i64::MIN);
+        let max = (viewport.max.0, viewport.max.1, i64::MAX);
+        steps_inside(start, step, length, min, max)
+    };
+    // Rows run from the top of the viewport down, so up is up
+    let mut mark = |coord: Coord, flag: u8| {
+        if viewport.contains(coord) {
+            let col = (coord.0 - viewport.min.0) / scale;
+            let row = (viewport.max.1 - coord.1) / scale;
+            cells[row as usize][col as usize] |= flag;
+        }
+    };
+
+    for (_, wire) in drawn(wires, follow) {
+        for segment in wire.iter() {
+            // Only the part of a horizontal or vertical segment inside the viewport is walked
+            if segment.is_horizontal() {
+                let ((z, fixed), lo, hi) = segment.span();
+                for x in lo.max(viewport.min.0)..=hi.min(viewport.max.0) {
+                    mark((x, fixed, z), HORIZONTAL);
+                }
+            } else if segment.is_vertical() {
+                let ((z, fixed), lo, hi) = segment.span();
+                for y in lo.max(viewport.min.1)..=hi.min(viewport.max.1) {
+                    mark((fixed, y, z), VERTICAL);
+                }
+            } else {
+                // Moves between layers are drawn like any other oblique move
+                let flag = match segment.step() {
+                    (1, 1, 0) | (-1, -1, 0) => RISING,
+                    (1, -1, 0) | (-1, 1, 0) => FALLING,
+                    _ => OBLIQUE,
+                };
+                if let Some((lo, hi)) = inside(segment.start, segment.step(), segment.length()) {
+                    for k in lo..=hi {
+                        mark(segment.at(k), flag);
+                    }
+                }
+            }
+        }
+        for pair in wire.windows(2) {
+            if cross(pair[0].step(), pair[1].step()) != (0, 0, 0) {
+                mark(pair[0].end, CORNER);
+            }
+        }
+    }
+    for run in shared_runs(wires) {
+        if follow.is_none_or(|f| run.segments.contains_key(&f)) {
+            if let Some((lo, hi)) = inside(run.from, run.step, run.length) {
+                for k in lo..=hi {
+                    mark(advance(run.from, run.step, k), CROSSING);
+                }
+            }
+        }
+    }
+    mark((0, 0, 0), ORIGIN);
+
+    let mut text = String::new();
+    for row in cells {
+        text.extend(row.into_iter().map(symbol));
+        text.push('\n');
+    }
+    text
+}
+
+fn symbol(cell: u8) -> char {
+    if cell & ORIGIN != 0 {
+        'o'
+    } else if cell & CROSSING != 0 {
+        'X'
+    } else if cell & CORNER != 0 || (cell & LINES).count_ones() > 1 {
+        '+'
+    } else if cell & HORIZONTAL != 0 {
+        '-'
+    } else if cell & VERTICAL != 0 {
+        '|'
+    } else if cell & RISING != 0 {
+        '/'
+    } else if cell & FALLING != 0 {
+        '\\'
+    } else if cell & OBLIQUE != 0 {
+        '*'
+    } else {
+        '.'
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day3::get_wire;
+
+    fn example() -> Vec<Wire> {
+        vec![
+            get_wire("R8,U5,L5,D3").unwrap(),
+            get_wire("U7,R6,D4,L4").unwrap(),
+        ]
+    }
+
+    #[test]
+    fn test_draw() {
+        let wires = example();
+        let expected = "\
+...........
+.+-----+...
+.|.....|...
+.|..+--X-+.
+.|..|..|.|.
+.|.-X--+.|.
+.|..|....|.
+.|.......|.
+.o-------+.
+...........
+";
+        assert_eq!(expected, draw(&wires, None, bounds(&wires, None), 1));
+    }
+
+    #[test]
+    fn test_draw_viewport() {
+        let wires = example();
+        let viewport = parse_viewport("2,2,7,5").unwrap();
+        assert_eq!(
+            ".+--X-\n.|..|.\n-X--+.\n.|....\n",
+            draw(&wires, None, viewport, 1)
+        );
+    }
+
+    #[test]
+    fn test_draw_downsampled() {
+        let wires = example();
+        assert_eq!(
+            "+--+..\n|.+X+.\n|-X+|.\n|.|.|.\no---+.\n",
+            draw(&wires, None, bounds(&wires, None), 2)
+        );
+    }
+
+    #[test]
+    fn test_draw_follow() {
+        let wires = example();
+        let expected = "\
+.........
+.+-----+.
+.|.....|.
+.|.....X.
+.|.....|.
+.|.-X--+.
+.|.......
+.|.......
+.o.......
+.........
+";
+        assert_eq!(expected, draw(&wires, Some(1), bounds(&wires, Some(1)), 1));
+    }
+
+    #[test]
+    fn test_draw_oblique() {
+        let wires = vec![get_wire("NE3,SE2").unwrap(), get_wire("R3,V-1,2").unwrap()];
+        let expected = "\
+........
+....+...
+...X.\\..
+../...\\.
+.o--+...
+........
+";
+        assert_eq!(expected, draw(&wires, None, bounds(&wires, None), 1));
+    }
+
+    #[test]
+    fn test_draw_long_overlap() {
+        let wires = vec![
+            get_wire("R1000000000").unwrap(),
+            get_wire("L1,R1000000001").unwrap(),
+        ];
+        let viewport = parse_viewport("-2,-1,4,0").unwrap();
+        assert_eq!(".-oXXXX\n.......\n", draw(&wires, None, viewport, 1));
+    }
+
+    #[test]
+    fn test_cells() {
+        assert_eq!((11, 10), bounds(&example(), None).cells(1));
+        assert_eq!((6, 5), bounds(&example(), None).cells(2));
+        // Viewports can be wider than an i64 can count
+        let viewport = parse_viewport("-9000000000000000000,0,9000000000000000000,0").unwrap();
+        assert_eq!((18_000_000_000_000_000_001, 1), viewport.cells(1));
+        let wires = vec![get_wire("R9223372036854775807").unwrap()];
+        assert_eq!((i64::MAX, 1), bounds(&wires, None).max);
+    }
+
+    #[test]
+    fn test_parse_viewport() {
+        assert_eq!(
+            Some(Viewport {
+                min: (-3, 1),
+                max: (5, 4)
+            }),
+            parse_viewport("5, 1, -3, 4")
+        );
+        assert_eq!(None, parse_viewport("1,2,3"));
+        assert_eq!(None, parse_viewport("1,2,3,x"));
+    }
+}
@@ -0,0 +1,378 @@
+use super::index::WireIndex;
+use super::{segment_crossing, Coord, Segment, Wire};
+use crate::rng::Rng;
+use std::collections::BTreeMap;
+use std::fs;
+
+// How many moves are tried for each segment when steering towards a number of intersections. Each
+// pair of moves tried can be at most half as long as the pair before, as short moves are the best
+// way to keep clear of other wires, and one of each pair heads towards a corner of an earlier
+// wire, to find more of them to cross.
+const CANDIDATES: usize = 16;
+
+pub struct Spec {
+    pub wires: usize,
+    pub segments: usize,
+    pub max_length: i64,
+    pub intersections: Option<usize>,
+}
+
+// Writes random wires, one per line, to `output`, or prints them if no file is given. The same
+// seed always gives the same wires.
+pub fn run(spec: &Spec, seed: u64, output: Option<&str>) {
+    if spec.max_length < 1 {
+        println!("The maximum segment length must be at least 1");
+        return;
+    }
+    // Every wire's steps, and so its coordinates, have to fit in an i64
+    let longest = (i64::MAX as u64 / spec.segments.max(1) as u64) as i64;
+    if spec.max_length > longest {
+        println!(
+            "The maximum segment length can be at most {} for {} segments",
+            longest, spec.segments
+        );
+        return;
+    }
+
+    let (wires, found) = generate(spec, &mut Rng::new(seed));
+    let mut text = String::new();
+    for wire in wires.iter() {
+        text.push_str(&to_line(wire));
+        text.push('\n');
+    }
+
+    match output {
+        Some(path) => match fs::write(path, text) {
+            Ok(()) => println!(
+                "Wrote {} wires with {} intersections to {}",
+                wires.len(),
+                found,
+                path
+            ),
+            Err(why) => println!("Couldn't write {}: {}", path, why),
+        },
+        None => print!("{}", text),
+    }
+}
+
+// Lays each wire down a segment at a time, turning at every corner so no move doubles back or
+// carries straight on. With a target number of intersections, the wires after the first pick
+// whichever of a few random moves brings the count closest to it. The target is only aimed for,
+// as wires can run out of room to cross, so the number of points the wires share is returned with
+// them.
+fn generate(spec: &Spec, rng: &mut Rng) -> (Vec<Wire>, usize) {
+    let mut wires: Vec<Wire> = vec![];
+    let mut index = WireIndex::new(vec![]);
+    let mut corners: Vec<Coord> = vec![];
+    let mut found = Found::new();
+
+    for w in 0..spec.wires {
+        let mut wire: Wire = vec![];
+        let mut at: Coord = (0, 0, 0);
+        let mut steps = 0;
+        let mut horizontal = rng.range(0, 1) == 0;
+
+        for _ in 0..spec.segments {
+            // A corner of an earlier wire to head for, to find more of them to cross
+            let towards = match corners.len() {
+                0 => at,
+                count => corners[rng.range(0, count as i64 - 1) as usize],
+            };
+            let mut candidate = |shrink: usize, homing: bool| {
+                let longest = (spec.max_length >> shrink.min(62)).max(1);
+                let (from, to) = if horizontal {
+                    (at.0, towards.0)
+                } else {
+                    (at.1, towards.1)
+                };
+                let sign = match rng.range(0, 1) {
+                    // Corners can be further apart than an i64 can count
+                    _ if homing && to > from => 1,
+                    _ if homing && to < from => -1,
+                    0 => 1,
+                    _ => -1,
+                };
+                let length = rng.range(1, longest) * sign;
+                let end = if horizontal {
+                    (at.0 + length, at.1, 0)
+                } else {
+                    (at.0, at.1 + length, 0)
+                };
+                Segment {
+                    start: at,
+                    end,
+                    steps,
+                }
+            };
+
+            let segment = match spec.intersections {
+                Some(target) if w > 0 => {
+                    let (segment, spans, new) = (0..CANDIDATES)
+                        .map(|k| {
+                            let segment = candidate(k / 2, k % 2 == 1);
+                            let spans = meets(&index, &wires, &segment);
+                            let new = found.new_points(&segment, &spans);
+                            (segment, spans, new)
+                        })
+                        .min_by_key(|&(_, _, new)| (found.count + new).abs_diff(target))
+                        .unwrap();
+                    found.add(&segment, spans, new);
+                    segment
+                }
+                _ => candidate(0, false),
+            };
+
+            at = segment.end;
+            steps += segment.length();
+            horizontal = !horizontal;
+            wire.push(segment);
+        }
+
+        // Without a target, the points this wire shares with earlier ones are counted afterwards
+        if spec.intersections.is_none() {
+            for segment in wire.iter() {
+                let spans = meets(&index, &wires, segment);
+                let new = found.new_points(segment, &spans);
+                found.add(segment, spans, new);
+            }
+        }
+        corners.extend(wire.iter().map(|segment| segment.end));
+        wires.push(wire);
+        index = WireIndex::new(wires.clone());
+    }
+    (wires, found.count)
+}
+
+// The stretches of a segment, as the first and last position along its line, where it meets the
+// wires laid so far. Only the earlier segments whose boxes it touches are checked.
+fn meets(index: &WireIndex, wires: &[Wire], segment: &Segment) -> Vec<(i64, i64)> {
+    let (_, lo, hi) = segment.span();
+    let along = |(x, y, _): Coord| if segment.is_horizontal() { x } else { y };
+    let (min, max) = (
+        segment.start.min(segment.end),
+        segment.start.max(segment.end),
+    );
+    let spans = index
+        .segments_in(min, max)
+        .into_iter()
+        .filter_map(|(w, i)| segment_crossing(segment, &wires[w][i]))
+        .map(|(from, to)| (along(from).max(lo), along(to).min(hi)))
+        .map(|(from, to)| (from.min(to), from.max(to)))
+        .collect();
+    merge(spans)
+}
+
+// Merged stretches of points on each line, by layer and the fixed coordinate of the line
+type Lines = BTreeMap<(i64, i64), Vec<(i64, i64)>>;
+
+// The points the wires are known to share, kept as stretches of the horizontal and vertical lines
+// they were found on, so long overlaps cost no more than short ones. A point where two lines cross
+// can be kept on both, so the count is kept alongside rather than worked out from the lines.
+struct Found {
+    rows: Lines,
+    columns: Lines,
+    count: usize,
+}
+
+impl Found {
+    // The origin isn't an intersection, so it starts off known and is never counted
+    fn new() -> Found {
+        let mut rows = BTreeMap::new();
+        rows.insert((0, 0), vec![(0, 0)]);
+        Found {
+            rows,
+            columns: BTreeMap::new(),
+            count: 0,
+        }
+    }
+
+    // The lines along a segment and across it
+    fn lines(&self, segment: &Segment) -> (&Lines, &Lines) {
+        if segment.is_horizontal() {
+            (&self.rows, &self.columns)
+        } else {
+            (&self.columns, &self.rows)
+        }
+    }
+
+    // How many of the points in `spans` along a segment's line aren't known yet
+    fn new_points(&self, segment: &Segment, spans: &[(i64, i64)]) -> usize {
+        let ((z, fixed), lo, hi) = segment.span();
+        let (along, across) = self.lines(segment);
+        let mut known = along.get(&(z, fixed)).cloned().unwrap_or_default();
+        for (&(_, at), stretches) in across.range((z, lo)..=(z, hi)) {
+            if stretches
+                .iter()
+                .any(|&(from, to)| from <= fixed && fixed <= to)
+            {
+                known.push((at, at));
+            }
+        }
+        (size(spans) - shared(spans, &merge(known))) as usize
+    }
+
+    fn add(&mut self, segment: &Segment, spans: Vec<(i64, i64)>, new: usize) {
+        let ((z, fixed), _, _) = segment.span();
+        let along = if segment.is_horizontal() {
+            &mut self.rows
+        } else {
+            &mut self.columns
+        };
+        let line = along.entry((z, fixed)).or_default();
+        line.extend(spans);
+        *line = merge(line.split_off(0));
+        self.count += new;
+    }
+}
+
+// Sorts stretches and joins any that overlap or sit end to end
+fn merge(mut spans: Vec<(i64, i64)>) -> Vec<(i64, i64)> {
+    spans.sort_unstable();
+    let mut merged: Vec<(i64, i64)> = vec![];
+    for (from, to) in spans {
+        match merged.last_mut() {
+            Some(last) if from <= last.1.saturating_add(1) => last.1 = last.1.max(to),
+            _ => merged.push((from, to)),
+        }
+    }
+    merged
+}
+
+fn size(spans: &[(i64, i64)]) -> u64 {
+    spans.iter().map(|&(from, to)| (to - from) as u64 + 1).sum()
+}
+
+// How many points two sets of merged stretches have in common
+fn shared(a: &[(i64, i64)], b: &[(i64, i64)]) -> u64 {
+    let (mut i, mut j, mut total) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        let (from, to) = (a[i].0.max(b[j].0), a[i].1.min(b[j].1));
+        if from <= to {
+            total += (to - from) as u64 + 1;
+        }
+        if a[i].1 < b[j].1 {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    total
+}
+
+fn to_line(wire: &Wire) -> String {
+    let moves: Vec<String> = wire
+        .iter()
+        .map(|segment| format!("{}{}", segment.direction(), segment.length()))
+        .collect();
+    moves.join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day3::{get_wire, shared_points};
+
+    fn spec() -> Spec {
+        Spec {
+            wires: 3,
+            segments: 40,
+            max_length: 20,
+            intersections: None,
+        }
+    }
+
+    #[test]
+    fn test_generate() {
+        let (wires, found) = generate(&spec(), &mut Rng::new(3));
+        assert_eq!(3, wires.len());
+
+        // The wires survive being written out and read back, and the count is of the points they
+        // share
+        let read: Vec<Wire> = wires
+            .iter()
+            .map(|wire| get_wire(&to_line(wire)).unwrap())
+            .collect();
+        assert_eq!(wires, read);
+        assert_eq!(shared_points(&read).len(), found);
+        for wire in read.iter() {
+            assert_eq!(40, wire.len());
+            for pair in wire.windows(2) {
+                assert!((1..=20).contains(&pair[0].length()));
+                assert_ne!(pair[0].is_horizontal(), pair[1].is_horizontal());
+            }
+        }
+
+        // Same seed, same wires
+        assert_eq!(wires, generate(&spec(), &mut Rng::new(3)).0);
+        assert_ne!(wires, generate(&spec(), &mut Rng::new(4)).0);
+    }
+
+    // Targets are hit closely when the wires have room to cross or keep clear of each other
+    #[test]
+    fn test_target() {
+        for &target in [0, 25, 100].iter() {
+            let spec = Spec {
+                wires: 3,
+                segments: 100,
+                max_length: 50,
+                intersections: Some(target),
+            };
+            let (wires, found) = generate(&spec, &mut Rng::new(2));
+            assert_eq!(shared_points(&wires).len(), found);
+            assert!(found.abs_diff(target) <= 2, "{} for {}", found, target);
+        }
+    }
+
+    // The longest moves allowed still keep every coordinate and step count in range
+    #[test]
+    fn test_longest() {
+        for &intersections in [None, Some(10)].iter() {
+            let spec = Spec {
+                wires: 3,
+                segments: 20,
+                max_length: i64::MAX / 20,
+                intersections,
+            };
+            for seed in 0..20 {
+                let (wires, _) = generate(&spec, &mut Rng::new(seed));
+                let read: Vec<Wire> = wires
+                    .iter()
+                    .map(|wire| get_wire(&to_line(wire)).unwrap())
+                    .collect();
+                assert_eq!(wires, read);
+            }
+        }
+    }
+
+    // Long overlaps are counted without walking them, and points already known aren't counted
+    // again from across the line
+    #[test]
+    fn test_found() {
+        let wires = vec![get_wire("R1000000000000,U2,L1000000000001").unwrap()];
+        let index = WireIndex::new(wires.clone());
+        let mut found = Found::new();
+        for (segment, expected) in [
+            ((-5, 0, 0), (2_000_000_000_000, 0, 0), 1_000_000_000_000),
+            ((7, -3, 0), (7, 3, 0), 1),
+            ((7, 2, 0), (9, 2, 0), 2),
+            ((-1, 5, 0), (-1, -5, 0), 1),
+        ]
+        .iter()
+        .map(|&(start, end, expected)| {
+            (
+                Segment {
+                    start,
+                    end,
+                    steps: 0,
+                },
+                expected,
+            )
+        }) {
+            let spans = meets(&index, &wires, &segment);
+            let new = found.new_points(&segment, &spans);
+            assert_eq!(expected, new);
+            found.add(&segment, spans, new);
+        }
+        assert_eq!(1_000_000_000_004, found.count);
+    }
+}
@@ -0,0 +1,447 @@
+use super::{gap, get_paths, parse_point, steps_along, Coord, Segment, Wire};
+
+// The most children an R-tree node holds
+const NODE_SIZE: usize = 8;
+
+// The smallest and largest corners of a box
+type Bounds = (Coord, Coord);
+
+struct Entry {
+    wire: usize,
+    index: usize,
+    bounds: Bounds,
+}
+
+enum Node {
+    Leaf(Bounds, Vec<Entry>),
+    Inner(Bounds, Vec<Node>),
+}
+
+impl Node {
+    fn bounds(&self) -> Bounds {
+        match self {
+            Node::Leaf(bounds, _) | Node::Inner(bounds, _) => *bounds,
+        }
+    }
+
+    fn search<'a>(&'a self, query: &Bounds, found: &mut Vec<&'a Entry>) {
+        if !overlaps(&self.bounds(), query) {
+            return;
+        }
+        match self {
+            Node::Leaf(_, entries) => found.extend(
+                entries
+                    .iter()
+                    .filter(|entry| overlaps(&entry.bounds, query)),
+            ),
+            Node::Inner(_, children) => {
+                for child in children.iter() {
+                    child.search(query, found);
+                }
+            }
+        }
+    }
+}
+
+// A set of wires that can be asked about points and areas without walking every wire. The boxes
+// around the segments are packed into an R-tree once, sort-tile-recursive style, so each question
+// only checks the segments near the place asked about.
+pub struct WireIndex {
+    wires: Vec<Wire>,
+    root: Option<Node>,
+}
+
+impl WireIndex {
+    pub(super) fn new(wires: Vec<Wire>) -> WireIndex {
+        let entries: Vec<Entry> = wires
+            .iter()
+            .enumerate()
+            .flat_map(|(wire, segments)| {
+                segments
+                    .iter()
+                    .enumerate()
+                    .map(move |(index, segment)| Entry {
+                        wire,
+                        index,
+                        bounds: segment_bounds(segment),
+                    })
+            })
+            .collect();
+
+        let mut level: Vec<Node> = pack(entries, |entry| entry.bounds)
+            .into_iter()
+            .map(|entries| Node::Leaf(enclose(entries.iter().map(|e| e.bounds)), entries))
+            .collect();
+        while level.len() > 1 {
+            level = pack(level, Node::bounds)
+                .into_iter()
+                .map(|nodes| Node::Inner(enclose(nodes.iter().map(Node::bounds)), nodes))
+                .collect();
+        }
+        WireIndex {
+            wires,
+            root: level.pop(),
+        }
+    }
+
+    fn search(&self, query: &Bounds) -> Vec<&Entry> {
+        let mut found = vec![];
+        if let Some(root) = &self.root {
+            root.search(query, &mut found);
+        }
+        found
+    }
+
+    fn segment(&self, entry: &Entry) -> &Segment {
+        &self.wires[entry.wire][entry.index]
+    }
+
+    // The segments that pass through a point
+    fn segments_at(&self, coord: Coord) -> impl Iterator<Item = &Entry> + '_ {
+        self.search(&(coord, coord))
+            .into_iter()
+            .filter(move |entry| contains(self.segment(entry), coord))
+    }
+
+    // Which wires pass through a point, in order
+    pub fn wires_at(&self, coord: Coord) -> Vec<usize> {
+        let mut wires: Vec<usize> = self.segments_at(coord).map(|entry| entry.wire).collect();
+        wires.sort_unstable();
+        wires.dedup();
+        wires
+    }
+
+    // The steps a wire takes to first reach a point, if it ever does
+    pub fn steps_at(&self, wire: usize, coord: Coord) -> Option<i64> {
+        self.segments_at(coord)
+            .filter(|entry| entry.wire == wire)
+            .map(|entry| self.segment(entry).steps_to(coord))
+            .min()
+    }
+
+    // The wire and position in it of every segment with a point inside a box, in order
+    pub fn segments_in(&self, min: Coord, max: Coord) -> Vec<(usize, usize)> {
+        let mut found: Vec<(usize, usize)> = self
+            .search(&(min, max))
+            .into_iter()
+            .filter(|entry| touches(self.segment(entry), min, max))
+            .map(|entry| (entry.wire, entry.index))
+            .collect();
+        found.sort_unstable();
+        found
+    }
+}
+
+// Looks up which wires pass through `point`, and after how many steps, and which segments have a
+// point inside `area`, given as `X1,Y1,X2,Y2` to cover every layer or `X1,Y1,Z1,X2,Y2,Z2`.
+pub fn report(input: &str, point: Option<&str>, area: Option<&str>) {
+    let wires = match get_paths(input) {
+        None => return,
+        Some(wires) => wires,
+    };
+    if point.is_none() && area.is_none() {
+        println!("You need to give a point or an area to look up");
+        return;
+    }
+    let index = WireIndex::new(wires);
+
+    if let Some(point) = point {
+        let coord = match parse_point(point) {
+            Some(coord) => coord,
+            None => {
+                println!("The point must be given as X,Y or X,Y,Z");
+                return;
+            }
+        };
+        let wires = index.wires_at(coord);
+        if wires.is_empty() {
+            println!("No wires pass through {:?}", coord);
+        }
+        for wire in wires {
+            println!(
+                "Wire {} passes through {:?} after {} steps",
+                wire + 1,
+                coord,
+                index.steps_at(wire, coord).unwrap()
+            );
+        }
+    }
+
+    if let Some(area) = area {
+        let (min, max) = match parse_area(area) {
+            Some(area) => area,
+            None => {
+                println!("The area must be given as X1,Y1,X2,Y2 or X1,Y1,Z1,X2,Y2,Z2");
+                return;
+            }
+        };
+        let found = index.segments_in(min, max);
+        println!("{} segments pass through the area", found.len());
+        for (wire, i) in found {
+            let segment = &index.wires[wire][i];
+            println!(
+                "  Wire {} segment {}: {:?} to {:?}, going {} after {} steps",
+                wire + 1,
+                i + 1,
+                segment.start,
+                segment.end,
+                segment.direction(),
+                segment.steps
+            );
+        }
+    }
+}
+
+fn parse_area(spec: &str) -> Option<Bounds> {
+    let values: Result<Vec<i64>, _> = spec.split(',').map(|v| v.trim().parse()).collect();
+    let (from, to) = match values.ok()?[..] {
+        [x1, y1, x2, y2] => ((x1, y1, i64::MIN), (x2, y2, i64::MAX)),
+        [x1, y1, z1, x2, y2, z2] => ((x1, y1, z1), (x2, y2, z2)),
+        _ => return None,
+    };
+    Some(enclose([(from, from), (to, to)].iter().cloned()))
+}
+
+// Groups items into nodes of up to `NODE_SIZE`. The items are sorted by x into vertical slices
+// about as many nodes wide as tall, then each slice is sorted by y and cut into nodes, so each
+// node covers a small, roughly square area.
+fn pack<T, F>(mut items: Vec<T>, bounds: F) -> Vec<Vec<T>>
+where
+    F: Fn(&T) -> Bounds,
+{
+    let nodes = items.len().div_ceil(NODE_SIZE);
+    let slices = (nodes as f64).sqrt().ceil() as usize;
+    let slice_size = slices.max(1) * NODE_SIZE;
+    // Twice the centre, which sorts the same, worked out wider as boxes can reach past half the
+    // number line
+    let centre = |b: Bounds| {
+        (
+            (b.0).0 as i128 + (b.1).0 as i128,
+            (b.0).1 as i128 + (b.1).1 as i128,
+        )
+    };
+
+    items.sort_by_key(|item| centre(bounds(item)).0);
+    let mut groups = vec![];
+    while !items.is_empty() {
+        let rest = items.split_off(slice_size.min(items.len()));
+        let mut slice = std::mem::replace(&mut items, rest);
+        slice.sort_by_key(|item| centre(bounds(item)).1);
+        while !slice.is_empty() {
+            let rest = slice.split_off(NODE_SIZE.min(slice.len()));
+            groups.push(std::mem::replace(&mut slice, rest));
+        }
+    }
+    groups
+}
+
+fn segment_bounds(segment: &Segment) -> Bounds {
+    enclose(
+        [(segment.start, segment.start), (segment.end, segment.end)]
+            .iter()
+            .cloned(),
+    )
+}
+
+// The smallest box holding all of the given boxes
+fn enclose<I: Iterator<Item = Bounds>>(boxes: I) -> Bounds {
+    boxes
+        .reduce(|(min1, max1), (min2, max2)| {
+            (
+                (min1.0.min(min2.0), min1.1.min(min2.1), min1.2.min(min2.2)),
+                (max1.0.max(max2.0), max1.1.max(max2.1), max1.2.max(max2.2)),
+            )
+        })
+        .unwrap_or(((0, 0, 0), (0, 0, 0)))
+}
+
+fn overlaps((min1, max1): &Bounds, (min2, max2): &Bounds) -> bool {
+    min1.0 <= max2.0
+        && min2.0 <= max1.0
+        && min1.1 <= max2.1
+        && min2.1 <= max1.1
+        && min1.2 <= max2.2
+        && min2.2 <= max1.2
+}
+
+fn contains(segment: &Segment, coord: Coord) -> bool {
+    match steps_along(gap(segment.start, coord), segment.step()) {
+        Some(k) => k >= 0 && k <= segment.length() as i128,
+        None => false,
+    }
+}
+
+// Whether any point of the segment lies in the box
+fn touches(segment: &Segment, min: Coord, max: Coord) -> bool {
+    steps_inside(segment.start, segment.step(), segment.length(), min, max).is_some()
+}
+
+// The first and last of the `length` steps of `step` on from `start` that land in the box, if any
+// do. A slanted run can pass the corner of a box between its points, so the range of steps that
+// stay inside is narrowed down along each axis in turn. The box can reach to the ends of the
+// number line, so the sums are done wider.
+pub(super) fn steps_inside(
+    start: Coord,
+    step: Coord,
+    length: i64,
+    min: Coord,
+    max: Coord,
+) -> Option<(i64, i64)> {
+    let axes = [
+        (start.0, step.0, min.0, max.0),
+        (start.1, step.1, min.1, max.1),
+        (start.2, step.2, min.2, max.2),
+    ];
+    let (mut lo, mut hi) = (0, length as i128);
+    for &(start, step, min, max) in axes.iter() {
+        let (start, step, min, max) = (start as i128, step as i128, min as i128, max as i128);
+        if step == 0 {
+            if start < min || start > max {
+                return None;
+            }
+            continue;
+        }
+        let (from, to) = if step > 0 {
+            (min - start, max - start)
+        } else {
+            (start - max, start - min)
+        };
+        let step = step.abs();
+        lo = lo.max(-(-from).div_euclid(step));
+        hi = hi.min(to.div_euclid(step));
+    }
+    if lo <= hi {
+        Some((lo as i64, hi as i64))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day3::{get_path, get_wire};
+
+    const LINES: [&str; 4] = [
+        "R8,U5,L5,D3",
+        "U7,R6,D4,L4",
+        "NE3,V2,-1,F2,SW2,V-3,1,-2",
+        "R2,U2,L1,D4,B1,R3",
+    ];
+
+    fn index() -> WireIndex {
+        WireIndex::new(LINES.iter().map(|line| get_wire(line).unwrap()).collect())
+    }
+
+    // Every point is checked against walking each wire a step at a time
+    #[test]
+    fn test_points() {
+        let index = index();
+        let paths: Vec<Vec<Coord>> = LINES.iter().map(|line| get_path(line).unwrap()).collect();
+        for x in -4..10 {
+            for y in -4..10 {
+                for z in -2..3 {
+                    let coord = (x, y, z);
+                    let expected: Vec<usize> = (0..paths.len())
+                        .filter(|&wire| paths[wire].contains(&coord))
+                        .collect();
+                    assert_eq!(expected, index.wires_at(coord), "at {:?}", coord);
+                    for (wire, path) in paths.iter().enumerate() {
+                        let steps = path.iter().position(|&c| c == coord).map(|s| s as i64);
+                        assert_eq!(steps, index.steps_at(wire, coord), "at {:?}", coord);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_segments_in() {
+        let index = index();
+        assert_eq!(
+            vec![(0, 2), (1, 2)],
+            index.segments_in((5, 4, 0), (7, 6, 0))
+        );
+        // The box around the diagonal overlaps this one, but none of its points are inside
+        assert_eq!(
+            Vec::<(usize, usize)>::new(),
+            index
+                .segments_in((2, 0, -1), (3, 1, 1))
+                .into_iter()
+                .filter(|&(wire, _)| wire == 2)
+                .collect::<Vec<_>>()
+        );
+        // Brute force every small box
+        let paths: Vec<Vec<Coord>> = LINES.iter().map(|line| get_path(line).unwrap()).collect();
+        let wires: Vec<Wire> = LINES.iter().map(|line| get_wire(line).unwrap()).collect();
+        for x in -3..8 {
+            for y in -3..8 {
+                let (min, max) = ((x, y, -1), (x + 2, y + 1, 1));
+                let inside = |c: &Coord| {
+                    c.0 >= min.0
+                        && c.0 <= max.0
+                        && c.1 >= min.1
+                        && c.1 <= max.1
+                        && c.2 >= min.2
+                        && c.2 <= max.2
+                };
+                let mut expected = vec![];
+                for (wire, segments) in wires.iter().enumerate() {
+                    for (i, segment) in segments.iter().enumerate() {
+                        let start = segment.steps as usize;
+                        let end = start + segment.length() as usize;
+                        if paths[wire][start..=end].iter().any(inside) {
+                            expected.push((wire, i));
+                        }
+                    }
+                }
+                assert_eq!(expected, index.segments_in(min, max), "in {:?}", (min, max));
+            }
+        }
+    }
+
+    #[test]
+    fn test_large() {
+        // Enough segments to need several levels of nodes
+        let line: Vec<String> = (1..200)
+            .map(|i| format!("{}{}", if i % 2 == 0 { "U" } else { "R" }, i % 7 + 1))
+            .collect();
+        let wire = get_wire(&line.join(",")).unwrap();
+        let last = *wire.last().unwrap();
+        let index = WireIndex::new(vec![wire]);
+        assert_eq!(vec![0], index.wires_at(last.end));
+        assert_eq!(
+            Some(last.steps + last.length()),
+            index.steps_at(0, last.end)
+        );
+        assert_eq!(None, index.steps_at(1, last.end));
+        assert!(index.wires_at((-1, 0, 0)).is_empty());
+
+        // Steps as long as the whole grid
+        let wire = get_wire("V3000000000000000001,3000000000000000000,NE1").unwrap();
+        let index = WireIndex::new(vec![wire]);
+        let far = (3_000_000_000_000_000_001, 3_000_000_000_000_000_000, 0);
+        assert_eq!(Some(1), index.steps_at(0, far));
+        assert!(index.wires_at((far.0 - 1, far.1 - 1, 0)).is_empty());
+        assert_eq!(vec![0], index.wires_at((far.0 + 1, far.1 + 1, 0)));
+
+        // Boxes past half the number line
+        let index = WireIndex::new(vec![
+            get_wire("U1,R9000000000000000000,D2").unwrap(),
+            get_wire("D1,R9000000000000000000,U2").unwrap(),
+        ]);
+        let far = (9_000_000_000_000_000_000, 0, 0);
+        assert_eq!(vec![0, 1], index.wires_at(far));
+        assert_eq!(Some(9_000_000_000_000_000_002), index.steps_at(1, far));
+    }
+
+    #[test]
+    fn test_parse_area() {
+        assert_eq!(
+            Some(((1, 2, i64::MIN), (5, 6, i64::MAX))),
+            parse_area("5,2,1,6")
+        );
+        assert_eq!(Some(((1, 2, -3), (4, 5, 6))), parse_area("4,2,6,1,5,-3"));
+        assert_eq!(None, parse_area("1,2,3"));
+    }
+}
@@ -0,0 +1,331 @@
+use super::metric::{nearest, Distance, Manhattan};
+use super::{get_paths, shared_runs, Coord, Wire};
+use std::fmt::Write;
+use std::fs;
+
+// How a wire passes through an intersection the first time it gets there
+#[derive(Debug, PartialEq)]
+struct Pass {
+    wire: usize,
+    steps: i64,
+    direction: String,
+}
+
+// A point where wires meet, or a run of them where wires overlap, from `coord` to `to`. The
+// distance is that of the closest point, and the passes are those at whichever end the wires
+// take fewer steps to reach in all, since that's where the least of them is.
+#[derive(Debug, PartialEq)]
+struct Intersection {
+    coord: Coord,
+    to: Coord,
+    distance: u128,
+    passes: Vec<Pass>,
+}
+
+impl Intersection {
+    // The steps every wire through the intersection takes to reach it. Each wire's steps fit in
+    // an i64, but the total might not.
+    fn steps(&self) -> i128 {
+        self.passes.iter().map(|pass| pass.steps as i128).sum()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortBy {
+    Position,
+    Distance,
+    Steps,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Table,
+    Csv,
+    Json,
+}
+
+// Lists every point where wires meet, with its manhattan distance and how each wire gets there,
+// and writes the list to `output`, or prints it if no file is given.
+pub fn export(input: &str, sort: SortBy, format: Format, output: Option<&str>) {
+    let wires = match get_paths(input) {
+        None => return,
+        Some(wires) => wires,
+    };
+
+    let found = intersections(&wires, sort);
+    let text = match format {
+        Format::Table => to_table(&found),
+        Format::Csv => to_csv(&found, wires.len()),
+        Format::Json => to_json(&found),
+    };
+    match output {
+        Some(path) => match fs::write(path, text) {
+            Ok(()) => println!("Wrote {} intersections to {}", found.len(), path),
+            Err(why) => println!("Couldn't write {}: {}", path, why),
+        },
+        None => print!("{}", text),
+    }
+}
+
+fn intersections(wires: &[Wire], sort: SortBy) -> Vec<Intersection> {
+    let mut found: Vec<Intersection> = shared_runs(wires)
+        .into_iter()
+        .map(|run| {
+            let end = *[run.from, run.to()]
+                .iter()
+                .min_by_key(|&&coord| (run.total_steps(coord), coord))
+                .unwrap();
+            Intersection {
+                coord: run.from,
+                to: run.to(),
+                distance: nearest(&Manhattan, (0, 0, 0), run.from, run.step, run.length)
+                    .into_iter()
+                    .map(|coord| Manhattan.distance((0, 0, 0), coord))
+                    .min()
+                    .unwrap(),
+                passes: run
+                    .first_segments(end)
+                    .into_iter()
+                    .map(|(wire, segment)| Pass {
+                        wire,
+                        steps: segment.steps_to(end),
+                        direction: segment.direction(),
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+    // Intersections come out in order of position, which also breaks ties in the other orders
+    match sort {
+        SortBy::Position => {}
+        SortBy::Distance => found.sort_by_key(|found| found.distance),
+        SortBy::Steps => found.sort_by_key(|found| found.steps()),
+    }
+    found
+}
+
+fn to_table(found: &[Intersection]) -> String {
+    let mut text = String::new();
+    for intersection in found {
+        let passes: Vec<String> = intersection
+            .passes
+            .iter()
+            .map(|pass| {
+                format!(
+                    "wire {} going {} after {}",
+                    pass.wire + 1,
+                    pass.direction,
+                    pass.steps
+                )
+            })
+            .collect();
+        let at = if intersection.to == intersection.coord {
+            format!("{:?}", intersection.coord)
+        } else {
+            format!("{:?} to {:?}", intersection.coord, intersection.to)
+        };
+        writeln!(
+            text,
+            "{}: distance {}, {} steps; {}",
+            at,
+            intersection.distance,
+            intersection.steps(),
+            passes.join(", ")
+        )
+        .unwrap();
+    }
+    text
+}
+
+// One row per intersection, from `x,y,z` to `to_x,to_y,to_z`, with a pair of columns for each
+// wire that are left empty if the wire doesn't pass through it
+fn to_csv(found: &[Intersection], wire_count: usize) -> String {
+    let mut text = String::from("x,y,z,to_x,to_y,to_z,distance,steps");
+    for wire in 1..=wire_count {
+        write!(text, ",wire{}_steps,wire{}_direction", wire, wire).unwrap();
+    }
+    text.push('\n');
+
+    for intersection in found {
+        write!(
+            text,
+            "{},{},{},{},{},{},{},{}",
+            intersection.coord.0,
+            intersection.coord.1,
+            intersection.coord.2,
+            intersection.to.0,
+            intersection.to.1,
+            intersection.to.2,
+            intersection.distance,
+            intersection.steps()
+        )
+        .unwrap();
+        for wire in 0..wire_count {
+            match intersection.passes.iter().find(|pass| pass.wire == wire) {
+                // Vector directions hold a comma, so they're quoted
+                Some(pass) if pass.direction.contains(',') => {
+                    write!(text, ",{},\"{}\"", pass.steps, pass.direction).unwrap()
+                }
+                Some(pass) => write!(text, ",{},{}", pass.steps, pass.direction).unwrap(),
+                None => text.push_str(",,"),
+            }
+        }
+        text.push('\n');
+    }
+    text
+}
+
+fn to_json(found: &[Intersection]) -> String {
+    let rows: Vec<String> = found
+        .iter()
+        .map(|intersection| {
+            let passes: Vec<String> = intersection
+                .passes
+                .iter()
+                .map(|pass| {
+                    format!(
+                        r#"{{"wire": {}, "steps": {}, "direction": "{}"}}"#,
+                        pass.wire + 1,
+                        pass.steps,
+                        pass.direction
+                    )
+                })
+                .collect();
+            format!(
+                r#"  {{"x": {}, "y": {}, "z": {}, "to": [{}, {}, {}], "distance": {}, "steps": {}, "wires": [{}]}}"#,
+                intersection.coord.0,
+                intersection.coord.1,
+                intersection.coord.2,
+                intersection.to.0,
+                intersection.to.1,
+                intersection.to.2,
+                intersection.distance,
+                intersection.steps(),
+                passes.join(", ")
+            )
+        })
+        .collect();
+
+    if rows.is_empty() {
+        String::from("[]\n")
+    } else {
+        format!("[\n{}\n]\n", rows.join(",\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day3::get_wire;
+
+    fn example() -> Vec<Wire> {
+        vec![
+            get_wire("R8,U5,L5,D3").unwrap(),
+            get_wire("U7,R6,D4,L4").unwrap(),
+        ]
+    }
+
+    #[test]
+    fn test_intersections() {
+        let found = intersections(&example(), SortBy::Position);
+        assert_eq!(
+            vec![
+                Intersection {
+                    coord: (3, 3, 0),
+                    to: (3, 3, 0),
+                    distance: 6,
+                    passes: vec![
+                        Pass {
+                            wire: 0,
+                            steps: 20,
+                            direction: String::from("D")
+                        },
+                        Pass {
+                            wire: 1,
+                            steps: 20,
+                            direction: String::from("L")
+                        },
+                    ],
+                },
+                Intersection {
+                    coord: (6, 5, 0),
+                    to: (6, 5, 0),
+                    distance: 11,
+                    passes: vec![
+                        Pass {
+                            wire: 0,
+                            steps: 15,
+                            direction: String::from("L")
+                        },
+                        Pass {
+                            wire: 1,
+                            steps: 15,
+                            direction: String::from("D")
+                        },
+                    ],
+                },
+            ],
+            found
+        );
+
+        let coords = |sort| -> Vec<Coord> {
+            intersections(&example(), sort)
+                .iter()
+                .map(|found| found.coord)
+                .collect()
+        };
+        assert_eq!(vec![(3, 3, 0), (6, 5, 0)], coords(SortBy::Distance));
+        assert_eq!(vec![(6, 5, 0), (3, 3, 0)], coords(SortBy::Steps));
+
+        // Both wires take more than half the steps an i64 can hold to get there
+        let wires = vec![
+            get_wire("U1,R9000000000000000000,D2").unwrap(),
+            get_wire("D1,R9000000000000000000,U2").unwrap(),
+        ];
+        let found = intersections(&wires, SortBy::Steps);
+        assert_eq!(18_000_000_000_000_000_004, found[0].steps());
+    }
+
+    #[test]
+    fn test_to_table() {
+        let found = intersections(&example(), SortBy::Steps);
+        assert_eq!(
+            "(6, 5, 0): distance 11, 30 steps; wire 1 going L after 15, wire 2 going D after 15\n\
+             (3, 3, 0): distance 6, 40 steps; wire 1 going D after 20, wire 2 going L after 20\n",
+            to_table(&found)
+        );
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let mut wires = example();
+        wires.push(get_wire("U3,R1").unwrap());
+        let found = intersections(&wires, SortBy::Distance);
+        let csv = to_csv(&found, 3);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            "x,y,z,to_x,to_y,to_z,distance,steps,wire1_steps,wire1_direction,wire2_steps,wire2_direction,wire3_steps,wire3_direction",
+            lines[0]
+        );
+        // The second and third wires run together up to (0, 3, 0), where the third turns off
+        assert_eq!("0,1,0,0,2,0,1,2,,,1,U,1,U", lines[1]);
+        assert_eq!("0,3,0,0,3,0,3,6,,,3,U,3,U", lines[2]);
+        assert_eq!("6,5,0,6,5,0,11,30,15,L,15,D,,", lines[lines.len() - 1]);
+    }
+
+    #[test]
+    fn test_to_json() {
+        assert_eq!("[]\n", to_json(&[]));
+        let found = intersections(&example(), SortBy::Position);
+        assert_eq!(
+            "[\n  \
+             {\"x\": 3, \"y\": 3, \"z\": 0, \"to\": [3, 3, 0], \"distance\": 6, \"steps\": 40, \"wires\": [\
+             {\"wire\": 1, \"steps\": 20, \"direction\": \"D\"}, \
+             {\"wire\": 2, \"steps\": 20, \"direction\": \"L\"}]},\n  \
+             {\"x\": 6, \"y\": 5, \"z\": 0, \"to\": [6, 5, 0], \"distance\": 11, \"steps\": 30, \"wires\": [\
+             {\"wire\": 1, \"steps\": 15, \"direction\": \"L\"}, \
+             {\"wire\": 2, \"steps\": 15, \"direction\": \"D\"}]}\n]\n",
+            to_json(&found)
+        );
+    }
+}
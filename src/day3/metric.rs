@@ -0,0 +1,268 @@
+use super::{advance, gap, get_paths, parse_point, shared_runs, Coord, Wire};
+use std::fmt;
+
+// A way of measuring how far apart two points are. Lengths are exact, so far-off points are
+// still told apart, and every metric grows steadily either side of its closest point along a
+// straight line, which is what lets runs of points be searched without visiting each one.
+pub trait Distance {
+    type Length: Copy + Ord + fmt::Debug + fmt::Display;
+
+    fn distance(&self, from: Coord, to: Coord) -> Self::Length;
+}
+
+pub struct Manhattan;
+pub struct Chebyshev;
+pub struct Euclidean;
+// Orders points the same way as `Euclidean`, but is a whole number
+pub struct SquaredEuclidean;
+
+// A sum of squares, which can be up to three times what a u128 holds, as the number of times it
+// wrapped around and what's left over, so they still compare in order
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Squared(u8, u128);
+
+impl fmt::Display for Squared {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Squared(0, rest) => write!(f, "{}", rest),
+            Squared(wraps, rest) => {
+                write!(f, "{}", f64::from(*wraps) * 2f64.powi(128) + *rest as f64)
+            }
+        }
+    }
+}
+
+// The square root of a sum of squares, compared by the square so it's exact, and only rounded
+// when it's shown
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Root(Squared);
+
+impl fmt::Display for Root {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Root(Squared(wraps, rest)) = self;
+        let square = f64::from(*wraps) * 2f64.powi(128) + *rest as f64;
+        write!(f, "{}", square.sqrt())
+    }
+}
+
+// How far apart two points are along each axis. Points can be anywhere, so the gaps are worked
+// out wider.
+fn gaps(from: Coord, to: Coord) -> [u128; 3] {
+    let (dx, dy, dz) = gap(from, to);
+    [dx.unsigned_abs(), dy.unsigned_abs(), dz.unsigned_abs()]
+}
+
+impl Distance for Manhattan {
+    type Length = u128;
+
+    fn distance(&self, from: Coord, to: Coord) -> u128 {
+        gaps(from, to).iter().sum()
+    }
+}
+
+impl Distance for Chebyshev {
+    type Length = u128;
+
+    fn distance(&self, from: Coord, to: Coord) -> u128 {
+        gaps(from, to).iter().cloned().max().unwrap()
+    }
+}
+
+impl Distance for Euclidean {
+    type Length = Root;
+
+    fn distance(&self, from: Coord, to: Coord) -> Root {
+        Root(SquaredEuclidean.distance(from, to))
+    }
+}
+
+impl Distance for SquaredEuclidean {
+    type Length = Squared;
+
+    fn distance(&self, from: Coord, to: Coord) -> Squared {
+        let mut total = Squared(0, 0);
+        for d in gaps(from, to).iter() {
+            let (rest, wrapped) = total.1.overflowing_add(d * d);
+            total = Squared(total.0 + wrapped as u8, rest);
+        }
+        total
+    }
+}
+
+// Finds the intersection of any two wires closest to `reference`, given as `X,Y` or `X,Y,Z`, by
+// the named metric.
+pub fn report(input: &str, metric_name: &str, reference: &str) {
+    let wires = match get_paths(input) {
+        None => return,
+        Some(wires) => wires,
+    };
+
+    let reference = match parse_point(reference) {
+        Some(reference) => reference,
+        None => {
+            println!("The reference point must be given as X,Y or X,Y,Z");
+            return;
+        }
+    };
+
+    match metric_name {
+        "manhattan" => print_closest(&wires, &Manhattan, metric_name, reference),
+        "chebyshev" => print_closest(&wires, &Chebyshev, metric_name, reference),
+        "euclidean" => print_closest(&wires, &Euclidean, metric_name, reference),
+        "squared-euclidean" => print_closest(&wires, &SquaredEuclidean, metric_name, reference),
+        _ => println!("Unknown metric: {}", metric_name),
+    }
+}
+
+fn print_closest<D: Distance>(wires: &[Wire], metric: &D, metric_name: &str, reference: Coord) {
+    match closest(wires, metric, reference) {
+        Some((coord, distance)) => println!(
+            "The closest intersection to {:?} by {} distance is {:?}, {} away",
+            reference, metric_name, coord, distance
+        ),
+        None => println!("There are no intersections"),
+    }
+}
+
+// Ties between intersections the same distance away go to the first by position
+fn closest<D: Distance>(
+    wires: &[Wire],
+    metric: &D,
+    reference: Coord,
+) -> Option<(Coord, D::Length)> {
+    shared_runs(wires)
+        .iter()
+        .flat_map(|run| nearest(metric, reference, run.from, run.step, run.length))
+        .map(|coord| (coord, metric.distance(reference, coord)))
+        .min_by_key(|&(coord, distance)| (distance, coord))
+}
+
+// The points of a run of `length` steps of `step` from `from` that are closest to `reference`,
+// and those either side of them, in case the closest is the origin, which is never an
+// intersection. Each step closer shrinks the distance until it starts to grow again, so the
+// point where it stops shrinking is searched for by halves.
+pub(super) fn nearest<D: Distance>(
+    metric: &D,
+    reference: Coord,
+    from: Coord,
+    step: Coord,
+    length: i64,
+) -> Vec<Coord> {
+    let cost = |k: i64| metric.distance(reference, advance(from, step, k));
+    let (mut lo, mut hi) = (0, length);
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if cost(mid + 1) >= cost(mid) {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+
+    let mut along = vec![(lo - 1).max(0), lo, (lo + 1).min(length)];
+    along.dedup();
+    along.into_iter().map(|k| advance(from, step, k)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day3::{closest_intersection_by_manhattan, get_wire, shared_points};
+
+    #[test]
+    fn test_distances() {
+        let (from, to) = ((1, 2, 0), (4, -2, 12));
+        assert_eq!(19, Manhattan.distance(from, to));
+        assert_eq!(12, Chebyshev.distance(from, to));
+        assert_eq!("13", Euclidean.distance(from, to).to_string());
+        assert_eq!(Squared(0, 169), SquaredEuclidean.distance(from, to));
+
+        // Distances across the whole grid are still exact
+        let (min, max) = (
+            (i64::MIN, i64::MIN, i64::MIN),
+            (i64::MAX, i64::MAX, i64::MAX),
+        );
+        let across = u64::MAX as u128;
+        assert_eq!(3 * across, Manhattan.distance(min, max));
+        assert_eq!(across, Chebyshev.distance(min, max));
+        let near = (i64::MAX, i64::MAX, i64::MAX - 1);
+        // Three times (2^64 - 1)^2 wraps around a u128 twice
+        assert_eq!(
+            Squared(2, u128::MAX - 3 * (1 << 65) + 4),
+            SquaredEuclidean.distance(min, max)
+        );
+        assert!(SquaredEuclidean.distance(min, near) < SquaredEuclidean.distance(min, max));
+        assert!(Euclidean.distance(min, near) < Euclidean.distance(min, max));
+        assert!(Manhattan.distance(min, near) < Manhattan.distance(min, max));
+    }
+
+    #[test]
+    fn test_parse_point() {
+        assert_eq!(Some((1, -2, 0)), parse_point("1,-2"));
+        assert_eq!(Some((1, -2, 3)), parse_point("1, -2, 3"));
+        assert_eq!(None, parse_point("1"));
+        assert_eq!(None, parse_point("1,2,3,4"));
+    }
+
+    #[test]
+    fn test_nearest() {
+        let nearest = |reference| nearest(&Euclidean, reference, (-50, 0, 0), (1, 0, 0), 100);
+        assert_eq!(
+            vec![(16, 0, 0), (17, 0, 0), (18, 0, 0)],
+            nearest((17, 5, 0))
+        );
+        assert_eq!(vec![(49, 0, 0), (50, 0, 0)], nearest((50, 50, 0)));
+        assert_eq!(vec![(-50, 0, 0), (-49, 0, 0)], nearest((-80, 3, 0)));
+        // The origin can be the closest point, so its neighbours are given too
+        assert_eq!(vec![(-1, 0, 0), (0, 0, 0), (1, 0, 0)], nearest((0, 0, 0)));
+
+        // Far enough out that a float can't tell neighbouring points apart
+        let far = 1 << 60;
+        assert_eq!(
+            vec![(far + 2, 0, 0), (far + 3, 0, 0), (far + 4, 0, 0)],
+            super::nearest(&Manhattan, (far + 3, 7, 0), (far, 0, 0), (1, 0, 0), 10)
+        );
+    }
+
+    // Every metric and reference point must find an intersection as close as the closest found by
+    // checking every point the wires share. Ties can be broken either way.
+    fn check_matches_shared_points<D: Distance>(wires: &[Wire], metric: &D) {
+        let points = shared_points(wires);
+        for &reference in [(0, 0, 0), (3, 2, 0), (100, -20, 0), (6, 6, 2)].iter() {
+            let expected = points
+                .keys()
+                .map(|&coord| metric.distance(reference, coord))
+                .min();
+            let found = closest(wires, metric, reference);
+            assert_eq!(expected, found.map(|(_, distance)| distance));
+            if let Some((coord, distance)) = found {
+                assert!(points.contains_key(&coord));
+                assert_eq!(distance, metric.distance(reference, coord));
+            }
+        }
+    }
+
+    #[test]
+    fn test_matches_shared_points() {
+        let cases = [
+            (
+                "R75,D30,R83,U83,L12,D49,R71,U7,L72",
+                "U62,R66,U55,R34,D71,R55,D58,R83",
+            ),
+            ("R10,U5,L10", "U1,R2,D1,R4,L1,NE4"),
+            ("NE6,SE6,F2,L12", "R12,U3,L12"),
+        ];
+        for (line1, line2) in cases.iter() {
+            let wires = vec![get_wire(line1).unwrap(), get_wire(line2).unwrap()];
+            check_matches_shared_points(&wires, &Manhattan);
+            check_matches_shared_points(&wires, &Chebyshev);
+            check_matches_shared_points(&wires, &Euclidean);
+            check_matches_shared_points(&wires, &SquaredEuclidean);
+
+            assert_eq!(
+                closest_intersection_by_manhattan(&wires[0], &wires[1]),
+                closest(&wires, &Manhattan, (0, 0, 0)).map(|(_, distance)| distance)
+            );
+        }
+    }
+}
@@ -0,0 +1,1928 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::convert::TryFrom;
+use std::fmt;
+use std::num::IntErrorKind;
+
+pub mod analysis;
+pub mod ascii;
+pub mod generate;
+pub mod index;
+pub mod intersections;
+pub mod metric;
+pub mod normalise;
+pub mod reroute;
+pub mod steps;
+pub mod svg;
+
+use metric::{nearest, Distance, Manhattan};
+
+// Why an instruction couldn't be read
+#[derive(Debug, PartialEq)]
+enum Error {
+    EmptyInstruction,
+    UnknownDirection,
+    MissingDistance,
+    BadDistance,
+    NegativeDistance,
+    DistanceOverflow,
+    TooManyDistances,
+}
+
+impl From<std::num::ParseIntError> for Error {
+    fn from(why: std::num::ParseIntError) -> Error {
+        match why.kind() {
+            IntErrorKind::Empty => Error::MissingDistance,
+            IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => Error::DistanceOverflow,
+            _ => Error::BadDistance,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match self {
+            Error::EmptyInstruction => "there's no instruction",
+            Error::UnknownDirection => "unknown direction",
+            Error::MissingDistance => "missing distance",
+            Error::BadDistance => "the distance isn't a number",
+            Error::NegativeDistance => "negative distance",
+            Error::DistanceOverflow => "the distance is too large",
+            Error::TooManyDistances => "a vector has two or three distances",
+        };
+        f.write_str(reason)
+    }
+}
+
+// An instruction that couldn't be read, and where it is. Lines and tokens count from 1, and a
+// vector's token includes the distances after it.
+#[derive(Debug, PartialEq)]
+struct ParseError {
+    line: usize,
+    token: usize,
+    text: String,
+    error: Error,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}, token {} ({:?}): {}",
+            self.line, self.token, self.text, self.error
+        )
+    }
+}
+
+// A move along the wire. Diagonal moves take one step for each point they cross diagonally, and
+// a vector move like `V3,-2` takes one step for each grid point it passes through, as a wire only
+// ever meets another at grid points. `F` and `B` (or `Z+` and `Z-`) move between layers, and a
+// vector can give a third distance to move between layers too.
+#[derive(Debug, PartialEq)]
+enum Instruction {
+    Up(i64),
+    Down(i64),
+    Left(i64),
+    Right(i64),
+    NorthEast(i64),
+    NorthWest(i64),
+    SouthEast(i64),
+    SouthWest(i64),
+    Forward(i64),
+    Back(i64),
+    Vector(i64, i64, i64),
+}
+
+impl TryFrom<&str> for Instruction {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Instruction, Self::Error> {
+        if value.is_empty() {
+            return Err(Error::EmptyInstruction);
+        }
+
+        if let Some(vector) = value.strip_prefix('V') {
+            let parts: Vec<&str> = vector.split(',').collect();
+            // The smallest i64 has no positive counterpart, so it can't be measured
+            let parse = |part: &str| match part.parse::<i64>()? {
+                i64::MIN => Err(Error::DistanceOverflow),
+                distance => Ok(distance),
+            };
+            return match parts[..] {
+                [x, y] => Ok(Instruction::Vector(parse(x)?, parse(y)?, 0)),
+                [x, y, z] => Ok(Instruction::Vector(parse(x)?, parse(y)?, parse(z)?)),
+                [x] => {
+                    parse(x)?;
+                    Err(Error::MissingDistance)
+                }
+                _ => Err(Error::TooManyDistances),
+            };
+        }
+
+        let split = value
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(value.len());
+        let (dir_part, dist_part) = value.split_at(split);
+        let instruction: fn(i64) -> Instruction = match dir_part {
+            "U" => Instruction::Up,
+            "D" => Instruction::Down,
+            "L" => Instruction::Left,
+            "R" => Instruction::Right,
+            "NE" => Instruction::NorthEast,
+            "NW" => Instruction::NorthWest,
+            "SE" => Instruction::SouthEast,
+            "SW" => Instruction::SouthWest,
+            "F" => Instruction::Forward,
+            "B" => Instruction::Back,
+            "Z" if dist_part.starts_with('+') => Instruction::Forward,
+            "Z" if dist_part.starts_with('-') => Instruction::Back,
+            _ => return Err(Error::UnknownDirection),
+        };
+        let dist = match dist_part.parse::<i64>()? {
+            // `Z-` moves back by the distance after the sign, which the smallest i64 doesn't have
+            dist if dist < 0 && dir_part == "Z" => {
+                dist.checked_neg().ok_or(Error::DistanceOverflow)?
+            }
+            dist if dist < 0 => return Err(Error::NegativeDistance),
+            dist => dist,
+        };
+        Ok(instruction(dist))
+    }
+}
+
+impl Instruction {
+    // How many steps the move takes, and the move made with each step
+    fn steps(&self) -> (i64, Coord) {
+        match *self {
+            Instruction::Up(d) => (d, (0, 1, 0)),
+            Instruction::Down(d) => (d, (0, -1, 0)),
+            Instruction::Left(d) => (d, (-1, 0, 0)),
+            Instruction::Right(d) => (d, (1, 0, 0)),
+            Instruction::NorthEast(d) => (d, (1, 1, 0)),
+            Instruction::NorthWest(d) => (d, (-1, 1, 0)),
+            Instruction::SouthEast(d) => (d, (1, -1, 0)),
+            Instruction::SouthWest(d) => (d, (-1, -1, 0)),
+            Instruction::Forward(d) => (d, (0, 0, 1)),
+            Instruction::Back(d) => (d, (0, 0, -1)),
+            Instruction::Vector(0, 0, 0) => (0, (0, 0, 0)),
+            Instruction::Vector(x, y, z) => {
+                let steps = gcd(gcd(x, y), z);
+                (steps, (x / steps, y / steps, z / steps))
+            }
+        }
+    }
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        let r = a % b;
+        a = b;
+        b = r;
+    }
+    a
+}
+
+// The cross product, which is zero for parallel moves. Steps can be as long as a whole move, so
+// it's worked out wider.
+fn cross(u: Coord, v: Coord) -> Wide {
+    let (u, v) = (widen(u), widen(v));
+    (
+        u.1 * v.2 - u.2 * v.1,
+        u.2 * v.0 - u.0 * v.2,
+        u.0 * v.1 - u.1 * v.0,
+    )
+}
+
+// How many of `step` make up `along`, if a whole number of them does
+fn steps_along(along: Wide, step: Coord) -> Option<i128> {
+    let step = widen(step);
+    let (a, s) = if step.0 != 0 {
+        (along.0, step.0)
+    } else if step.1 != 0 {
+        (along.1, step.1)
+    } else if step.2 != 0 {
+        (along.2, step.2)
+    } else {
+        return if along == (0, 0, 0) { Some(0) } else { None };
+    };
+    if a % s != 0 {
+        return None;
+    }
+    let k = a / s;
+    let fits = |a: i128, s: i128| k.checked_mul(s) == Some(a);
+    if fits(along.0, step.0) && fits(along.1, step.1) && fits(along.2, step.2) {
+        Some(k)
+    } else {
+        None
+    }
+}
+
+// `(a * b - c * d) / n`, if it's a whole number. Each product fits in an i128, but their
+// difference can be twice as large, so it's worked out as a sign and a size.
+fn exact_quotient(a: i128, b: i128, c: i128, d: i128, n: i128) -> Option<i128> {
+    let (x, y) = (a * b, c * d);
+    let (negative, size) = if (x < 0) == (y < 0) {
+        ((x - y) < 0, (x - y).unsigned_abs())
+    } else {
+        (x < 0, x.unsigned_abs() + y.unsigned_abs())
+    };
+    if size % n.unsigned_abs() != 0 {
+        return None;
+    }
+    let quotient = i128::try_from(size / n.unsigned_abs()).ok()?;
+    Some(if negative != (n < 0) {
+        -quotient
+    } else {
+        quotient
+    })
+}
+
+// The move from one point to another
+fn offset(from: Coord, to: Coord) -> Coord {
+    (to.0 - from.0, to.1 - from.1, to.2 - from.2)
+}
+
+// The move from one point to another, worked out wider for points that can be any distance apart
+fn gap(from: Coord, to: Coord) -> Wide {
+    let (from, to) = (widen(from), widen(to));
+    (to.0 - from.0, to.1 - from.1, to.2 - from.2)
+}
+
+// The point `k` moves of `step` on from `from`
+fn advance(from: Coord, step: Coord, k: i64) -> Coord {
+    (
+        from.0 + k * step.0,
+        from.1 + k * step.1,
+        from.2 + k * step.2,
+    )
+}
+
+// x, y and the layer z, which is 0 for wires that never change layer
+type Coord = (i64, i64, i64);
+
+// A straight run of wire. `steps` is how far along the wire `start` is. Most runs are horizontal
+// or vertical within a layer, but diagonal, vector and layer moves give oblique ones.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Segment {
+    start: Coord,
+    end: Coord,
+    steps: i64,
+}
+
+impl Segment {
+    fn is_horizontal(&self) -> bool {
+        self.start.1 == self.end.1 && self.start.2 == self.end.2
+    }
+
+    fn is_vertical(&self) -> bool {
+        self.start.0 == self.end.0 && self.start.2 == self.end.2
+    }
+
+    // The move the wire makes with each step along the segment
+    fn step(&self) -> Coord {
+        let (dx, dy, dz) = offset(self.start, self.end);
+        let length = self.length();
+        (dx / length, dy / length, dz / length)
+    }
+
+    // How many steps the segment takes
+    fn length(&self) -> i64 {
+        let (dx, dy, dz) = offset(self.start, self.end);
+        gcd(gcd(dx, dy), dz)
+    }
+
+    // The point `k` steps along the segment
+    fn at(&self, k: i64) -> Coord {
+        advance(self.start, self.step(), k)
+    }
+
+    // The line the segment lies on and the range it covers along it. The line is the layer and y
+    // for a horizontal segment, with the range of x, and the layer and x for a vertical one, with
+    // the range of y. Oblique segments have no span.
+    fn span(&self) -> ((i64, i64), i64, i64) {
+        if self.is_horizontal() {
+            (
+                (self.start.2, self.start.1),
+                self.start.0.min(self.end.0),
+                self.start.0.max(self.end.0),
+            )
+        } else {
+            (
+                (self.start.2, self.start.0),
+                self.start.1.min(self.end.1),
+                self.start.1.max(self.end.1),
+            )
+        }
+    }
+
+    // The steps along the wire to reach a point on this segment
+    fn steps_to(&self, coord: Coord) -> i64 {
+        let step = self.step();
+        if step.0 != 0 {
+            self.steps + (coord.0 - self.start.0) / step.0
+        } else if step.1 != 0 {
+            self.steps + (coord.1 - self.start.1) / step.1
+        } else {
+            self.steps + (coord.2 - self.start.2) / step.2
+        }
+    }
+
+    // The direction the wire runs along this segment, as written in its instructions. Oblique
+    // segments other than diagonals are given as the vector of a single step.
+    fn direction(&self) -> String {
+        let direction = match self.step() {
+            (0, 1, 0) => "U",
+            (0, -1, 0) => "D",
+            (-1, 0, 0) => "L",
+            (1, 0, 0) => "R",
+            (1, 1, 0) => "NE",
+            (-1, 1, 0) => "NW",
+            (1, -1, 0) => "SE",
+            (-1, -1, 0) => "SW",
+            (0, 0, 1) => "F",
+            (0, 0, -1) => "B",
+            (x, y, 0) => return format!("V{},{}", x, y),
+            (x, y, z) => return format!("V{},{},{}", x, y, z),
+        };
+        direction.to_string()
+    }
+}
+
+type Wire = Vec<Segment>;
+
+// A run of points shared by one segment from each of two wires. Perpendicular segments share a
+// single point, so `from` and `to` are equal, but segments along the same line can share many.
+#[derive(Debug, PartialEq)]
+struct Crossing {
+    from: Coord,
+    to: Coord,
+    segment1: Segment,
+    segment2: Segment,
+}
+
+pub fn part1(input: &str) {
+    let wires = match get_paths(input) {
+        None => return,
+        Some(wires) => wires,
+    };
+
+    let closest = pairs(&wires)
+        .filter_map(|(i, j)| closest_intersection_by_manhattan(&wires[i], &wires[j]))
+        .min();
+    match closest {
+        Some(dist) => println!(
+            "The closest intersection by manhattan distance is {} units away",
+            dist
+        ),
+        None => println!("There are no intersections"),
+    }
+}
+
+pub fn part2(input: &str) {
+    let wires = match get_paths(input) {
+        None => return,
+        Some(wires) => wires,
+    };
+
+    let closest = pairs(&wires)
+        .filter_map(|(i, j)| closest_intersection_by_steps(&wires[i], &wires[j]))
+        .min();
+    match closest {
+        Some(dist) => println!(
+            "The closest intersection by stepping along the paths is {} units away",
+            dist
+        ),
+        None => println!("There are no intersections"),
+    }
+}
+
+fn get_paths(input: &str) -> Option<Vec<Wire>> {
+    let wires = parse_wires(input)?;
+    if wires.len() < 2 {
+        println!("Expected at least two input paths");
+        return None;
+    }
+
+    Some(wires)
+}
+
+// Builds a wire from every line of the input, or says why it can't
+fn parse_wires(input: &str) -> Option<Vec<Wire>> {
+    match parse_lines(input, get_wire) {
+        Ok(wires) => Some(wires),
+        Err(errors) => {
+            print_errors(&errors);
+            None
+        }
+    }
+}
+
+// Parses every line of the input, or finds every instruction that couldn't be read
+fn parse_lines<T, F>(input: &str, parse: F) -> Result<Vec<T>, Vec<ParseError>>
+where
+    F: Fn(&str) -> Result<T, Vec<ParseError>>,
+{
+    let mut parsed = vec![];
+    let mut errors = vec![];
+    for (i, line) in input.lines().enumerate() {
+        match parse(line) {
+            Ok(value) => parsed.push(value),
+            Err(found) => errors.extend(found.into_iter().map(|error| ParseError {
+                line: i + 1,
+                ..error
+            })),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(parsed)
+    } else {
+        Err(errors)
+    }
+}
+
+fn print_errors(errors: &[ParseError]) {
+    println!("Could not parse input instructions:");
+    for error in errors {
+        println!("  {}", error);
+    }
+}
+
+// Reads a point given as `X,Y` in the starting layer, or `X,Y,Z`
+fn parse_point(spec: &str) -> Option<Coord> {
+    let values: Result<Vec<i64>, _> = spec.split(',').map(|v| v.trim().parse()).collect();
+    match values.ok()?[..] {
+        [x, y] => Some((x, y, 0)),
+        [x, y, z] => Some((x, y, z)),
+        _ => None,
+    }
+}
+
+// Every pair of wire indexes, each pair once
+fn pairs(wires: &[Wire]) -> impl Iterator<Item = (usize, usize)> {
+    let n = wires.len();
+    (0..n).flat_map(move |i| ((i + 1)..n).map(move |j| (i, j)))
+}
+
+// Reports the closest intersection of every pair of wires, then the points crossed by at least
+// `min_wires` wires and the closest of those.
+pub fn crossings_report(input: &str, min_wires: usize) {
+    let wires = match get_paths(input) {
+        None => return,
+        Some(wires) => wires,
+    };
+
+    for (i, j) in pairs(&wires) {
+        match (
+            closest_intersection_by_manhattan(&wires[i], &wires[j]),
+            closest_intersection_by_steps(&wires[i], &wires[j]),
+        ) {
+            (Some(dist), Some(steps)) => println!(
+                "Wires {} and {}: closest intersection is {} units away by manhattan distance and {} by steps",
+                i + 1,
+                j + 1,
+                dist,
+                steps
+            ),
+            _ => println!("Wires {} and {}: no intersections", i + 1, j + 1),
+        }
+    }
+
+    let runs = shared_runs(&wires);
+    let count = count_shared(&runs, min_wires);
+    println!(
+        "{} points are crossed by at least {} wires",
+        count, min_wires
+    );
+
+    let closest = [
+        (
+            "manhattan distance",
+            closest_shared_by_manhattan(&runs, min_wires).map(|(coord, d)| (coord, d.to_string())),
+        ),
+        (
+            "steps",
+            closest_shared_by_steps(&runs, min_wires).map(|(coord, s)| (coord, s.to_string())),
+        ),
+    ];
+    for (name, found) in closest.iter() {
+        if let Some((coord, cost)) = found {
+            let run = runs.iter().find(|run| run.contains(*coord)).unwrap();
+            let through: Vec<String> = run
+                .segments
+                .keys()
+                .map(|wire| (wire + 1).to_string())
+                .collect();
+            println!(
+                "Closest by {}: {:?} at {}, crossed by wires {}",
+                name,
+                coord,
+                cost,
+                through.join(", ")
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+fn parse_instructions(line: &str) -> Result<Vec<Instruction>, Vec<ParseError>> {
+    let tokens = parse_tokens(line)?;
+    Ok(tokens
+        .into_iter()
+        .map(|(_, _, instruction)| instruction)
+        .collect())
+}
+
+// Parses each instruction in a line, along with its token index and text, or gives every
+// instruction that couldn't be read. Errors are on line 1, for the caller to move.
+fn parse_tokens(line: &str) -> Result<Vec<(usize, String, Instruction)>, Vec<ParseError>> {
+    let is_number = |token: &(usize, &str)| {
+        token
+            .1
+            .starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '+')
+    };
+    let mut tokens = line.split(',').enumerate().peekable();
+    let mut instructions = vec![];
+    let mut errors = vec![];
+    while let Some((i, token)) = tokens.next() {
+        // A vector move carries its other distances in the tokens after it
+        let mut text = token.to_string();
+        if token.starts_with('V') {
+            while let Some((_, distance)) = tokens.next_if(is_number) {
+                text.push(',');
+                text.push_str(distance);
+            }
+        }
+        match Instruction::try_from(text.as_str()) {
+            Ok(instruction) => instructions.push((i + 1, text, instruction)),
+            Err(error) => errors.push(ParseError {
+                line: 1,
+                token: i + 1,
+                text,
+                error,
+            }),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(instructions)
+    } else {
+        Err(errors)
+    }
+}
+
+// Builds a wire from a line of instructions, leaving out moves that go nowhere. Moves that would
+// take the wire further than a coordinate or step count can hold are errors.
+fn get_wire(line: &str) -> Result<Wire, Vec<ParseError>> {
+    let mut wire = vec![];
+    let mut curr = (0, 0, 0);
+    let mut steps: i64 = 0;
+
+    for (token, text, instruction) in parse_tokens(line)? {
+        let (dist, step) = instruction.steps();
+        if dist <= 0 {
+            continue;
+        }
+
+        let along = |from: i64, step: i64| step.checked_mul(dist)?.checked_add(from);
+        let end = match (
+            along(curr.0, step.0),
+            along(curr.1, step.1),
+            along(curr.2, step.2),
+            steps.checked_add(dist),
+        ) {
+            (Some(x), Some(y), Some(z), Some(_)) => (x, y, z),
+            // Nothing after this can be placed either
+            _ => {
+                return Err(vec![ParseError {
+                    line: 1,
+                    token,
+                    text,
+                    error: Error::DistanceOverflow,
+                }])
+            }
+        };
+        wire.push(Segment {
+            start: curr,
+            end,
+            steps,
+        });
+        curr = end;
+        steps += dist;
+    }
+
+    Ok(wire)
+}
+
+// The corners of a wire, starting at the origin.
+fn vertices(wire: &Wire) -> Vec<Coord> {
+    let mut corners = vec![(0, 0, 0)];
+    corners.extend(wire.iter().map(|segment| segment.end));
+    corners
+}
+
+// Expands a line of instructions into every point the wire visits.
+#[cfg(test)]
+fn get_path(line: &str) -> Result<Vec<Coord>, Vec<ParseError>> {
+    let mut path = vec![(0, 0, 0)];
+    for i in parse_instructions(line)?.iter() {
+        path = apply_instruction(i, path);
+    }
+    Ok(path)
+}
+
+// Returns the closest intersection of the two wires by calculating the manhattan distance of the
+// intersection from the origin.
+fn closest_intersection_by_manhattan(wire1: &Wire, wire2: &Wire) -> Option<u128> {
+    closest_intersection(
+        wire1,
+        wire2,
+        |from, step, length| nearest(&Manhattan, (0, 0, 0), from, step, length),
+        |coord, _, _| Manhattan.distance((0, 0, 0), coord),
+    )
+}
+
+// Returns the closest intersection of the two wires by calculating the distance along the wires.
+// Each wire's steps fit in an i64, but the two together might not.
+fn closest_intersection_by_steps(wire1: &Wire, wire2: &Wire) -> Option<i128> {
+    closest_intersection(wire1, wire2, ends, |coord, segment1, segment2| {
+        segment1.steps_to(coord) as i128 + segment2.steps_to(coord) as i128
+    })
+}
+
+// Returns the smallest cost of any intersection other than the origin, checking the points of
+// each run of crossings that `candidates` says the cost can be smallest at. A point can lie on
+// several segments of the same wire, and the cost is taken from whichever pair gives the least,
+// so step counts follow the first time each wire reaches the point.
+fn closest_intersection<T, C, F>(wire1: &Wire, wire2: &Wire, candidates: C, cost: F) -> Option<T>
+where
+    T: Ord,
+    C: Fn(Coord, Coord, i64) -> Vec<Coord>,
+    F: Fn(Coord, &Segment, &Segment) -> T,
+{
+    crossings(wire1, wire2)
+        .iter()
+        .flat_map(|crossing| {
+            let (length, step) = crossing.steps();
+            candidates(crossing.from, step, length)
+                .into_iter()
+                .filter(|&coord| coord != (0, 0, 0))
+                .map(move |coord| (coord, crossing))
+        })
+        .map(|(coord, crossing)| cost(coord, &crossing.segment1, &crossing.segment2))
+        .min()
+}
+
+// The steps each wire takes to first reach a point only grow or shrink along a run of `length`
+// steps from `from`, so their total is smallest at one end or the other. If the run passes the
+// origin, which is never an intersection, the points either side of it are the ends of what's
+// left.
+fn ends(from: Coord, step: Coord, length: i64) -> Vec<Coord> {
+    let mut along = vec![0, length];
+    if let Some(k) = steps_along(gap(from, (0, 0, 0)), step) {
+        if k >= 0 && k <= length as i128 {
+            let k = k as i64;
+            along.extend([(k - 1).max(0), (k + 1).min(length)].iter());
+        }
+    }
+    along.sort_unstable();
+    along.dedup();
+    along.into_iter().map(|k| advance(from, step, k)).collect()
+}
+
+impl Crossing {
+    // How many steps it is from one end of the crossing to the other, and the move each step
+    // makes
+    fn steps(&self) -> (i64, Coord) {
+        let (dx, dy, dz) = offset(self.from, self.to);
+        match gcd(gcd(dx, dy), dz) {
+            0 => (0, (0, 0, 0)),
+            length => (length, (dx / length, dy / length, dz / length)),
+        }
+    }
+
+    fn points(&self) -> Vec<Coord> {
+        let (length, step) = self.steps();
+        (0..=length).map(|k| advance(self.from, step, k)).collect()
+    }
+}
+
+// Finds every run of points the two wires share.
+fn crossings(wire1: &Wire, wire2: &Wire) -> Vec<Crossing> {
+    let mut found = perpendicular_crossings(wire1, wire2, false);
+    found.extend(perpendicular_crossings(wire2, wire1, true));
+    found.extend(collinear_crossings(wire1, wire2, true));
+    found.extend(collinear_crossings(wire1, wire2, false));
+    found.extend(oblique_crossings(wire1, wire2));
+    found
+}
+
+// Events at the same x are handled in this order, so segments that only touch end to end still
+// count as crossing.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+enum Event {
+    Start,
+    Vertical,
+    End,
+}
+
+// Finds where horizontal segments of `across` cross vertical segments of `up`, by sweeping a
+// line from left to right. While the line is over a horizontal segment it is kept in a set
+// ordered by layer and y, so each vertical segment finds the ones it spans in its own layer with
+// a range query.
+// `swapped` says that `across` is the second wire rather than the first.
+fn perpendicular_crossings(across: &Wire, up: &Wire, swapped: bool) -> Vec<Crossing> {
+    let mut events = vec![];
+    for (i, segment) in across.iter().enumerate() {
+        if segment.is_horizontal() {
+            let (_, lo, hi) = segment.span();
+            events.push((lo, Event::Start, i));
+            events.push((hi, Event::End, i));
+        }
+    }
+    for (i, segment) in up.iter().enumerate() {
+        if segment.is_vertical() {
+            events.push((segment.start.0, Event::Vertical, i));
+        }
+    }
+    events.sort_unstable();
+
+    let mut active = BTreeSet::new();
+    let mut found = vec![];
+    for (x, event, i) in events {
+        match event {
+            Event::Start => {
+                active.insert((across[i].start.2, across[i].start.1, i));
+            }
+            Event::End => {
+                active.remove(&(across[i].start.2, across[i].start.1, i));
+            }
+            Event::Vertical => {
+                let ((z, _), lo, hi) = up[i].span();
+                for &(_, y, j) in active.range((z, lo, 0)..=(z, hi, usize::MAX)) {
+                    let (segment1, segment2) = if swapped {
+                        (up[i], across[j])
+                    } else {
+                        (across[j], up[i])
+                    };
+                    found.push(Crossing {
+                        from: (x, y, z),
+                        to: (x, y, z),
+                        segment1,
+                        segment2,
+                    });
+                }
+            }
+        }
+    }
+
+    found
+}
+
+// Finds where segments of the two wires overlap along the same horizontal (or vertical) line in
+// the same layer.
+// Segments are sorted by line and then by where they start, and swept along each line keeping
+// those that haven't ended yet.
+fn collinear_crossings(wire1: &Wire, wire2: &Wire, horizontal: bool) -> Vec<Crossing> {
+    let mut spans = vec![];
+    for (n, wire) in [wire1, wire2].iter().enumerate() {
+        for segment in wire.iter() {
+            if (horizontal && segment.is_horizontal()) || (!horizontal && segment.is_vertical()) {
+                let (fixed, lo, hi) = segment.span();
+                spans.push((fixed, lo, hi, n, *segment));
+            }
+        }
+    }
+    spans.sort_unstable_by_key(|&(fixed, lo, hi, n, _)| (fixed, lo, hi, n));
+
+    let mut found = vec![];
+    let mut active: Vec<((i64, i64), i64, usize, Segment)> = vec![];
+    for (fixed, lo, hi, n, segment) in spans {
+        active.retain(|&(line, end, _, _)| line == fixed && end >= lo);
+        for &(_, end, other_n, other) in active.iter() {
+            if other_n == n {
+                continue;
+            }
+            let to = hi.min(end);
+            let (z, fixed) = fixed;
+            let (from, to) = if horizontal {
+                ((lo, fixed, z), (to, fixed, z))
+            } else {
+                ((fixed, lo, z), (fixed, to, z))
+            };
+            let (segment1, segment2) = if n == 0 {
+                (segment, other)
+            } else {
+                (other, segment)
+            };
+            found.push(Crossing {
+                from,
+                to,
+                segment1,
+                segment2,
+            });
+        }
+        active.push((fixed, hi, n, segment));
+    }
+
+    found
+}
+
+// Finds where oblique segments, including moves between layers, meet any segment of the other
+// wire. There are few enough of them to check each against every segment, skipping those whose
+// bounding boxes don't overlap.
+fn oblique_crossings(wire1: &Wire, wire2: &Wire) -> Vec<Crossing> {
+    let bounds = |segment: &Segment| {
+        let (a, b) = (segment.start, segment.end);
+        (
+            (a.0.min(b.0), a.1.min(b.1), a.2.min(b.2)),
+            (a.0.max(b.0), a.1.max(b.1), a.2.max(b.2)),
+        )
+    };
+    let is_oblique = |segment: &Segment| !segment.is_horizontal() && !segment.is_vertical();
+
+    let mut found = vec![];
+    for segment1 in wire1.iter() {
+        let (lo1, hi1) = bounds(segment1);
+        for segment2 in wire2.iter() {
+            if !is_oblique(segment1) && !is_oblique(segment2) {
+                continue;
+            }
+            let (lo2, hi2) = bounds(segment2);
+            if lo1.0 > hi2.0
+                || lo2.0 > hi1.0
+                || lo1.1 > hi2.1
+                || lo2.1 > hi1.1
+                || lo1.2 > hi2.2
+                || lo2.2 > hi1.2
+            {
+                continue;
+            }
+            if let Some((from, to)) = segment_crossing(segment1, segment2) {
+                found.push(Crossing {
+                    from,
+                    to,
+                    segment1: *segment1,
+                    segment2: *segment2,
+                });
+            }
+        }
+    }
+
+    found
+}
+
+// The run of grid points two segments share, if any. Segments that cross between grid points,
+// as two diagonals can, don't share a point.
+fn segment_crossing(a: &Segment, b: &Segment) -> Option<(Coord, Coord)> {
+    let (step_a, step_b) = (a.step(), b.step());
+    let between = gap(a.start, b.start);
+    let normal = cross(step_a, step_b);
+
+    if normal == (0, 0, 0) {
+        // Parallel segments only meet if they lie on the same line, and then share the part
+        // where their ranges along it overlap
+        let k1 = steps_along(between, step_a)?;
+        let k2 = k1 + steps_along(gap(b.start, b.end), step_a)?;
+        let (lo, hi) = (k1.min(k2).max(0), k1.max(k2).min(a.length() as i128));
+        if lo > hi {
+            return None;
+        }
+        return Some((a.at(lo as i64), a.at(hi as i64)));
+    }
+
+    // Solves `a.start + k * step_a == b.start + j * step_b` for whole numbers of steps in the
+    // plane of two axes the lines aren't parallel in, then checks the point on each is the same
+    // in the third, as lines in different planes pass each other without meeting
+    let (s_a, s_b) = (widen(step_a), widen(step_b));
+    let (k, j) = if normal.2 != 0 {
+        (
+            exact_quotient(between.0, s_b.1, between.1, s_b.0, normal.2)?,
+            exact_quotient(between.0, s_a.1, between.1, s_a.0, normal.2)?,
+        )
+    } else if normal.1 != 0 {
+        (
+            exact_quotient(between.2, s_b.0, between.0, s_b.2, normal.1)?,
+            exact_quotient(between.2, s_a.0, between.0, s_a.2, normal.1)?,
+        )
+    } else {
+        (
+            exact_quotient(between.1, s_b.2, between.2, s_b.1, normal.0)?,
+            exact_quotient(between.1, s_a.2, between.2, s_a.1, normal.0)?,
+        )
+    };
+    if k < 0 || k > a.length() as i128 || j < 0 || j > b.length() as i128 {
+        return None;
+    }
+    let point = a.at(k as i64);
+    if point != b.at(j as i64) {
+        return None;
+    }
+    Some((point, point))
+}
+
+#[cfg(test)]
+fn apply_instruction(instruction: &Instruction, mut path: Vec<Coord>) -> Vec<Coord> {
+    let curr = *path.last().unwrap();
+
+    match instruction {
+        Instruction::Up(d) => {
+            for i in (curr.1 + 1)..((curr.1 + 1) + d) {
+                path.push((curr.0, i, curr.2));
+            }
+        }
+        Instruction::Down(d) => {
+            for i in ((curr.1 - d)..(curr.1)).rev() {
+                path.push((curr.0, i, curr.2));
+            }
+        }
+        Instruction::Left(d) => {
+            for i in ((curr.0 - d)..(curr.0)).rev() {
+                path.push((i, curr.1, curr.2));
+            }
+        }
+        Instruction::Right(d) => {
+            for i in (curr.0 + 1)..((curr.0 + 1) + d) {
+                path.push((i, curr.1, curr.2));
+            }
+        }
+        _ => {
+            let (dist, step) = instruction.steps();
+            for k in 1..=dist {
+                path.push(advance(curr, step, k));
+            }
+        }
+    }
+    path
+}
+
+// Every wire that passes through a point, and the steps it takes to first get there
+type WiresAtPoint = BTreeMap<usize, i64>;
+
+// Every wire that passes through a point, and the segment it first gets there along
+type SegmentsAtPoint = BTreeMap<usize, Segment>;
+
+// A run of points where wires meet, each `step` on from the one before, that the same segments of
+// the same wires pass through. Where wires overlap, the points between those where any wire
+// joins, leaves or crosses the overlap make up one run, so they don't have to be listed one by
+// one. Most runs are a single point.
+#[derive(Debug, PartialEq)]
+struct SharedRun {
+    from: Coord,
+    step: Coord,
+    length: i64,
+    // The segments each wire passes through the run along, in the order the wire takes them
+    segments: BTreeMap<usize, Vec<Segment>>,
+}
+
+impl SharedRun {
+    fn to(&self) -> Coord {
+        advance(self.from, self.step, self.length)
+    }
+
+    // How many points the run covers
+    fn size(&self) -> u64 {
+        self.length as u64 + 1
+    }
+
+    fn points(&self) -> impl Iterator<Item = Coord> + '_ {
+        (0..=self.length).map(move |k| advance(self.from, self.step, k))
+    }
+
+    fn first_segments(&self, coord: Coord) -> SegmentsAtPoint {
+        self.segments
+            .iter()
+            .map(|(&wire, segments)| {
+                let first = segments
+                    .iter()
+                    .min_by_key(|segment| segment.steps_to(coord))
+                    .unwrap();
+                (wire, *first)
+            })
+            .collect()
+    }
+
+    fn steps_at(&self, coord: Coord) -> WiresAtPoint {
+        self.first_segments(coord)
+            .into_iter()
+            .map(|(wire, segment)| (wire, segment.steps_to(coord)))
+            .collect()
+    }
+
+    // The steps every wire through the run takes to first reach a point, added up. Each wire's
+    // steps fit in an i64, but the total might not.
+    fn total_steps(&self, coord: Coord) -> i128 {
+        self.steps_at(coord)
+            .values()
+            .map(|&steps| steps as i128)
+            .sum()
+    }
+
+    fn contains(&self, coord: Coord) -> bool {
+        match steps_along(gap(self.from, coord), self.step) {
+            Some(k) => k >= 0 && k <= self.length as i128,
+            None => false,
+        }
+    }
+}
+
+// A run of points two wires share along a line, between `lo` and `hi` by position along it
+struct Overlap {
+    lo: i128,
+    hi: i128,
+    start: Coord,
+    through: [(usize, Segment); 2],
+}
+
+// A line, as the step along it, pointing the same way whichever way a wire runs, and the moment
+// of its points about the origin, which is the same for every point on the line. Both are worked
+// out wider so far-off lines don't overflow.
+type Line = (Coord, Wide);
+
+type Wide = (i128, i128, i128);
+
+fn widen(coord: Coord) -> Wide {
+    (coord.0 as i128, coord.1 as i128, coord.2 as i128)
+}
+
+fn line_through(coord: Coord, step: Coord) -> Line {
+    let (c, s) = (widen(coord), widen(step));
+    let moment = (
+        c.1 * s.2 - c.2 * s.1,
+        c.2 * s.0 - c.0 * s.2,
+        c.0 * s.1 - c.1 * s.0,
+    );
+    (step, moment)
+}
+
+// How far along a line going `step` a point is, measured along the first axis the line moves
+// along, so neighbouring points on the line are the size of the step on that axis apart
+fn position(coord: Coord, step: Coord) -> i128 {
+    let (c, s) = (widen(coord), widen(step));
+    if s.0 != 0 {
+        c.0 * s.0.signum()
+    } else if s.1 != 0 {
+        c.1 * s.1.signum()
+    } else {
+        c.2 * s.2.signum()
+    }
+}
+
+// Adds the segments of the wires through a point or run to those already found
+fn add_segments<'a, I>(segments: &mut BTreeMap<usize, Vec<Segment>>, through: I)
+where
+    I: IntoIterator<Item = &'a (usize, Segment)>,
+{
+    for &(wire, segment) in through {
+        let found = segments.entry(wire).or_default();
+        if !found.contains(&segment) {
+            found.push(segment);
+        }
+    }
+}
+
+// Finds every run of points other than the origin where at least two wires meet. Crossings that
+// share a single point are gathered by the point, and overlaps by the line they lie on. Each line
+// is then cut wherever an overlap starts or ends, or at any of the single points that lie on it,
+// and the pieces between the cuts become runs.
+fn shared_runs(wires: &[Wire]) -> Vec<SharedRun> {
+    let mut points: BTreeMap<Coord, BTreeMap<usize, Vec<Segment>>> = BTreeMap::new();
+    let mut lines: HashMap<Line, Vec<Overlap>> = HashMap::new();
+    for (i, j) in pairs(wires) {
+        for crossing in crossings(&wires[i], &wires[j]) {
+            let through = [(i, crossing.segment1), (j, crossing.segment2)];
+            let (length, step) = crossing.steps();
+            if length == 0 {
+                add_segments(points.entry(crossing.from).or_default(), &through);
+                continue;
+            }
+            let (start, step) = if step > (0, 0, 0) {
+                (crossing.from, step)
+            } else {
+                (crossing.to, (-step.0, -step.1, -step.2))
+            };
+            let lo = position(start, step);
+            let hi = lo + length as i128 * position(step, step);
+            let overlap = Overlap {
+                lo,
+                hi,
+                start,
+                through,
+            };
+            lines
+                .entry(line_through(start, step))
+                .or_default()
+                .push(overlap);
+        }
+    }
+
+    // The single points, and the origin, that lie on each line
+    let steps: BTreeSet<Coord> = lines.keys().map(|&(step, _)| step).collect();
+    let mut cuts: HashMap<Line, Vec<i128>> = HashMap::new();
+    for &coord in points.keys().chain(std::iter::once(&(0, 0, 0))) {
+        for &step in steps.iter() {
+            let line = line_through(coord, step);
+            if lines.contains_key(&line) {
+                cuts.entry(line).or_default().push(position(coord, step));
+            }
+        }
+    }
+
+    let mut runs = vec![];
+    for (line, mut overlaps) in lines {
+        let step = line.0;
+        let unit = position(step, step);
+        let mut cuts = cuts.remove(&line).unwrap_or_default();
+        cuts.extend(
+            overlaps
+                .iter()
+                .flat_map(|overlap| vec![overlap.lo, overlap.hi]),
+        );
+        cuts.sort_unstable();
+        cuts.dedup();
+        overlaps.sort_unstable_by_key(|overlap| overlap.lo);
+
+        let at = |overlap: &Overlap, pos: i128| {
+            advance(overlap.start, step, ((pos - overlap.lo) / unit) as i64)
+        };
+        let mut active: Vec<&Overlap> = vec![];
+        let mut next = overlaps.iter().peekable();
+        for (n, &cut) in cuts.iter().enumerate() {
+            while let Some(overlap) = next.next_if(|overlap| overlap.lo <= cut) {
+                active.push(overlap);
+            }
+            active.retain(|overlap| overlap.hi >= cut);
+            if let Some(first) = active.first() {
+                let through = active.iter().flat_map(|overlap| overlap.through.iter());
+                add_segments(points.entry(at(first, cut)).or_default(), through);
+            }
+
+            // The points strictly between this cut and the next are covered by the overlaps
+            // that carry on past this one
+            let after = match cuts.get(n + 1) {
+                Some(&after) if after - cut > unit => after,
+                _ => continue,
+            };
+            let covering: Vec<&&Overlap> = active.iter().filter(|o| o.hi > cut).collect();
+            if let Some(first) = covering.first() {
+                let mut segments = BTreeMap::new();
+                add_segments(
+                    &mut segments,
+                    covering.iter().flat_map(|overlap| overlap.through.iter()),
+                );
+                runs.push(SharedRun {
+                    from: at(first, cut + unit),
+                    step,
+                    length: ((after - cut) / unit - 2) as i64,
+                    segments,
+                });
+            }
+        }
+    }
+
+    runs.extend(
+        points
+            .into_iter()
+            .filter(|&(coord, _)| coord != (0, 0, 0))
+            .map(|(from, segments)| SharedRun {
+                from,
+                step: (0, 0, 0),
+                length: 0,
+                segments,
+            }),
+    );
+    for run in runs.iter_mut() {
+        for segments in run.segments.values_mut() {
+            segments.sort_unstable_by_key(|segment| segment.steps);
+        }
+    }
+    runs.sort_unstable_by_key(|run| run.from);
+    runs
+}
+
+// Every point other than the origin where at least two wires meet, one by one
+#[cfg(test)]
+fn shared_points(wires: &[Wire]) -> BTreeMap<Coord, WiresAtPoint> {
+    shared_runs(wires)
+        .iter()
+        .flat_map(|run| run.points().map(move |coord| (coord, run.steps_at(coord))))
+        .collect()
+}
+
+// How many points are crossed by at least `min_wires` wires
+fn count_shared(runs: &[SharedRun], min_wires: usize) -> u64 {
+    runs.iter()
+        .filter(|run| run.segments.len() >= min_wires)
+        .map(SharedRun::size)
+        .sum()
+}
+
+// Returns the closest point crossed by at least `min_wires` wires by manhattan distance, and how
+// far away it is
+fn closest_shared_by_manhattan(runs: &[SharedRun], min_wires: usize) -> Option<(Coord, u128)> {
+    closest_shared(
+        runs,
+        min_wires,
+        |run| nearest(&Manhattan, (0, 0, 0), run.from, run.step, run.length),
+        |_, coord| Manhattan.distance((0, 0, 0), coord),
+    )
+}
+
+// Returns the closest point crossed by at least `min_wires` wires by the total steps every wire
+// through the point takes to reach it, and the total
+fn closest_shared_by_steps(runs: &[SharedRun], min_wires: usize) -> Option<(Coord, i128)> {
+    closest_shared(
+        runs,
+        min_wires,
+        |run| ends(run.from, run.step, run.length),
+        |run, coord| run.total_steps(coord),
+    )
+}
+
+fn closest_shared<T, C, F>(
+    runs: &[SharedRun],
+    min_wires: usize,
+    candidates: C,
+    cost: F,
+) -> Option<(Coord, T)>
+where
+    T: Copy + Ord,
+    C: Fn(&SharedRun) -> Vec<Coord>,
+    F: Fn(&SharedRun, Coord) -> T,
+{
+    runs.iter()
+        .filter(|run| run.segments.len() >= min_wires)
+        .flat_map(|run| {
+            candidates(run)
+                .into_iter()
+                .map(|coord| (coord, cost(run, coord)))
+                .collect::<Vec<_>>()
+        })
+        .min_by_key(|&(coord, cost)| (cost, coord))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_try_instruction_from_str() {
+        assert_eq!(Err(Error::EmptyInstruction), Instruction::try_from(""));
+        assert_eq!(Err(Error::MissingDistance), Instruction::try_from("U"));
+        assert_eq!(Err(Error::UnknownDirection), Instruction::try_from("UD"));
+        assert_eq!(Err(Error::UnknownDirection), Instruction::try_from("X1"));
+        assert_eq!(Ok(Instruction::Up(1)), Instruction::try_from("U1"));
+        assert_eq!(Ok(Instruction::Down(10)), Instruction::try_from("D10"));
+        assert_eq!(Ok(Instruction::Left(100)), Instruction::try_from("L100"));
+        assert_eq!(Ok(Instruction::Right(1000)), Instruction::try_from("R1000"));
+        assert_eq!(Ok(Instruction::NorthEast(5)), Instruction::try_from("NE5"));
+        assert_eq!(Ok(Instruction::SouthWest(2)), Instruction::try_from("SW2"));
+        assert_eq!(
+            Ok(Instruction::Vector(3, -2, 0)),
+            Instruction::try_from("V3,-2")
+        );
+        assert_eq!(Err(Error::UnknownDirection), Instruction::try_from("N5"));
+        assert_eq!(Err(Error::MissingDistance), Instruction::try_from("NE"));
+        assert_eq!(Err(Error::MissingDistance), Instruction::try_from("V3"));
+        assert_eq!(Err(Error::BadDistance), Instruction::try_from("V3,x"));
+        assert_eq!(Ok(Instruction::Forward(1)), Instruction::try_from("F1"));
+        assert_eq!(Ok(Instruction::Back(4)), Instruction::try_from("B4"));
+        assert_eq!(Ok(Instruction::Forward(2)), Instruction::try_from("Z+2"));
+        assert_eq!(Ok(Instruction::Back(3)), Instruction::try_from("Z-3"));
+        assert_eq!(Err(Error::UnknownDirection), Instruction::try_from("Z3"));
+        assert_eq!(
+            Ok(Instruction::Vector(1, 2, -3)),
+            Instruction::try_from("V1,2,-3")
+        );
+        assert_eq!(
+            Err(Error::TooManyDistances),
+            Instruction::try_from("V1,2,3,4")
+        );
+        assert_eq!(Err(Error::BadDistance), Instruction::try_from("R1x"));
+        assert_eq!(Err(Error::NegativeDistance), Instruction::try_from("U-3"));
+        assert_eq!(Err(Error::NegativeDistance), Instruction::try_from("F-1"));
+        assert_eq!(
+            Err(Error::DistanceOverflow),
+            Instruction::try_from("R99999999999999999999")
+        );
+        assert_eq!(
+            Err(Error::DistanceOverflow),
+            Instruction::try_from("Z-9223372036854775808")
+        );
+        assert_eq!(
+            Ok(Instruction::Back(9223372036854775807)),
+            Instruction::try_from("Z-9223372036854775807")
+        );
+        assert_eq!(
+            Err(Error::DistanceOverflow),
+            Instruction::try_from("V-9223372036854775808,0")
+        );
+        assert_eq!(
+            Err(Error::DistanceOverflow),
+            Instruction::try_from("V1,2,-9223372036854775808")
+        );
+    }
+
+    #[test]
+    fn test_parse_instructions() {
+        assert_eq!(
+            Ok(vec![
+                Instruction::Right(2),
+                Instruction::Vector(3, -2, 0),
+                Instruction::NorthWest(1)
+            ]),
+            parse_instructions("R2,V3,-2,NW1")
+        );
+        assert_eq!(
+            Ok(vec![Instruction::Vector(1, 2, 3), Instruction::Forward(4)]),
+            parse_instructions("V1,2,3,F4")
+        );
+        assert_eq!(
+            Err(vec![ParseError {
+                line: 1,
+                token: 2,
+                text: String::from("V3"),
+                error: Error::MissingDistance
+            }]),
+            parse_instructions("R2,V3")
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        // Every bad instruction in a line is found, counting vectors as one token
+        let errors = parse_instructions("R2,X4,V1,-1,U,D-2,,L3").unwrap_err();
+        let found: Vec<(usize, &str, &Error)> = errors
+            .iter()
+            .map(|e| (e.token, e.text.as_str(), &e.error))
+            .collect();
+        assert_eq!(
+            vec![
+                (2, "X4", &Error::UnknownDirection),
+                (5, "U", &Error::MissingDistance),
+                (6, "D-2", &Error::NegativeDistance),
+                (7, "", &Error::EmptyInstruction),
+            ],
+            found
+        );
+        assert_eq!(
+            "line 1, token 6 (\"D-2\"): negative distance",
+            errors[2].to_string()
+        );
+
+        // Distances that fit on their own can still take the wire too far
+        let max = i64::MAX;
+        let errors = get_wire(&format!("R{},R1,U1", max)).unwrap_err();
+        assert_eq!(
+            vec![ParseError {
+                line: 1,
+                token: 2,
+                text: String::from("R1"),
+                error: Error::DistanceOverflow
+            }],
+            errors
+        );
+    }
+
+    #[test]
+    fn test_parse_lines() {
+        let count = |line: &str| parse_instructions(line).map(|found| found.len());
+        assert_eq!(Ok(vec![2, 1]), parse_lines("R1,U1\nL1", count));
+
+        let errors = parse_lines("R1\nQ1\nR1,Z2", count).unwrap_err();
+        let found: Vec<(usize, usize)> = errors.iter().map(|e| (e.line, e.token)).collect();
+        assert_eq!(vec![(2, 1), (3, 2)], found);
+    }
+
+    #[test]
+    fn test_apply_instruction() {
+        assert_eq!(
+            vec![(0, 0, 0)],
+            apply_instruction(&Instruction::Up(0), vec![(0, 0, 0)])
+        );
+        assert_eq!(
+            vec![(0, 0, 0)],
+            apply_instruction(&Instruction::Down(0), vec![(0, 0, 0)])
+        );
+        assert_eq!(
+            vec![(0, 0, 0)],
+            apply_instruction(&Instruction::Left(0), vec![(0, 0, 0)])
+        );
+        assert_eq!(
+            vec![(0, 0, 0)],
+            apply_instruction(&Instruction::Right(0), vec![(0, 0, 0)])
+        );
+        assert_eq!(
+            vec![(0, 0, 0), (0, 1, 0)],
+            apply_instruction(&Instruction::Up(1), vec![(0, 0, 0)])
+        );
+        assert_eq!(
+            vec![(0, 0, 0), (0, -1, 0)],
+            apply_instruction(&Instruction::Down(1), vec![(0, 0, 0)])
+        );
+        assert_eq!(
+            vec![(0, 0, 0), (-1, 0, 0)],
+            apply_instruction(&Instruction::Left(1), vec![(0, 0, 0)])
+        );
+        assert_eq!(
+            vec![(0, 0, 0), (1, 0, 0)],
+            apply_instruction(&Instruction::Right(1), vec![(0, 0, 0)])
+        );
+        assert_eq!(
+            vec![
+                (0, 0, 0),
+                (0, 1, 0),
+                (0, 2, 0),
+                (0, 3, 0),
+                (0, 4, 0),
+                (0, 5, 0)
+            ],
+            apply_instruction(&Instruction::Up(5), vec![(0, 0, 0)])
+        );
+        assert_eq!(
+            vec![
+                (0, 0, 0),
+                (0, -1, 0),
+                (0, -2, 0),
+                (0, -3, 0),
+                (0, -4, 0),
+                (0, -5, 0)
+            ],
+            apply_instruction(&Instruction::Down(5), vec![(0, 0, 0)])
+        );
+        assert_eq!(
+            vec![
+                (0, 0, 0),
+                (-1, 0, 0),
+                (-2, 0, 0),
+                (-3, 0, 0),
+                (-4, 0, 0),
+                (-5, 0, 0)
+            ],
+            apply_instruction(&Instruction::Left(5), vec![(0, 0, 0)])
+        );
+        assert_eq!(
+            vec![
+                (0, 0, 0),
+                (1, 0, 0),
+                (2, 0, 0),
+                (3, 0, 0),
+                (4, 0, 0),
+                (5, 0, 0)
+            ],
+            apply_instruction(&Instruction::Right(5), vec![(0, 0, 0)])
+        );
+    }
+
+    #[test]
+    fn test_get_path() {
+        assert_eq!(Err(Error::BadDistance), Instruction::try_from("U1,"));
+        assert_eq!(Ok(vec![(0, 0, 0), (0, 1, 0)]), get_path("U1"));
+        assert_eq!(Ok(vec![(0, 0, 0), (0, 1, 0), (0, 0, 0)]), get_path("U1,D1"));
+        assert_eq!(
+            Ok(vec![
+                (0, 0, 0),
+                (1, 0, 0),
+                (2, 0, 0),
+                (3, 0, 0),
+                (4, 0, 0),
+                (5, 0, 0),
+                (6, 0, 0),
+                (7, 0, 0),
+                (8, 0, 0),
+                (8, 1, 0),
+                (8, 2, 0),
+                (8, 3, 0),
+                (8, 4, 0),
+                (8, 5, 0),
+                (7, 5, 0),
+                (6, 5, 0),
+                (5, 5, 0),
+                (4, 5, 0),
+                (3, 5, 0),
+                (3, 4, 0),
+                (3, 3, 0),
+                (3, 2, 0)
+            ]),
+            get_path("R8,U5,L5,D3")
+        );
+    }
+
+    #[test]
+    fn test_get_wire() {
+        assert_eq!(Ok(vec![]), get_wire("U0"));
+        assert_eq!(
+            Ok(vec![
+                Segment {
+                    start: (0, 0, 0),
+                    end: (8, 0, 0),
+                    steps: 0
+                },
+                Segment {
+                    start: (8, 0, 0),
+                    end: (8, 5, 0),
+                    steps: 8
+                },
+                Segment {
+                    start: (8, 5, 0),
+                    end: (3, 5, 0),
+                    steps: 13
+                },
+                Segment {
+                    start: (3, 5, 0),
+                    end: (3, 2, 0),
+                    steps: 18
+                },
+            ]),
+            get_wire("R8,U0,U5,L5,D3")
+        );
+
+        // A vector move steps through each grid point on its way
+        assert_eq!(Ok(vec![]), get_wire("V0,0,NE0"));
+        let wire = get_wire("NE2,V4,-2").unwrap();
+        assert_eq!(
+            vec![
+                Segment {
+                    start: (0, 0, 0),
+                    end: (2, 2, 0),
+                    steps: 0
+                },
+                Segment {
+                    start: (2, 2, 0),
+                    end: (6, 0, 0),
+                    steps: 2
+                },
+            ],
+            wire
+        );
+        assert_eq!((2, -1, 0), wire[1].step());
+        assert_eq!(3, wire[1].steps_to((4, 1, 0)));
+        assert_eq!("NE", wire[0].direction());
+        assert_eq!("V2,-1", wire[1].direction());
+    }
+
+    #[test]
+    fn test_layers() {
+        let wire = get_wire("R2,F3,U1,Z-1").unwrap();
+        assert_eq!(
+            vec![(0, 0, 0), (2, 0, 0), (2, 0, 3), (2, 1, 3), (2, 1, 2)],
+            vertices(&wire)
+        );
+        assert_eq!("F", wire[1].direction());
+        assert_eq!("B", wire[3].direction());
+        assert_eq!(4, wire[1].steps_to((2, 0, 2)));
+        assert_eq!(7, Manhattan.distance((0, 0, 0), (2, -1, -4)));
+
+        // Wires only meet where they share a layer as well as x and y
+        let wire1 = get_wire("R4").unwrap();
+        let wire2 = get_wire("F1,R2,B1,R2").unwrap();
+        let points: Vec<Coord> = shared_points(&[wire1, wire2]).keys().cloned().collect();
+        assert_eq!(vec![(2, 0, 0), (3, 0, 0), (4, 0, 0)], points);
+    }
+
+    #[test]
+    fn test_segment_crossing() {
+        let segment = |start, end| Segment {
+            start,
+            end,
+            steps: 0,
+        };
+        // Diagonals that cross between grid points don't meet
+        assert_eq!(
+            None,
+            segment_crossing(
+                &segment((0, 0, 0), (1, 1, 0)),
+                &segment((1, 0, 0), (0, 1, 0))
+            )
+        );
+        assert_eq!(
+            Some(((1, 1, 0), (1, 1, 0))),
+            segment_crossing(
+                &segment((0, 0, 0), (2, 2, 0)),
+                &segment((2, 0, 0), (0, 2, 0))
+            )
+        );
+        assert_eq!(
+            Some(((2, 1, 0), (2, 1, 0))),
+            segment_crossing(
+                &segment((0, 0, 0), (4, 2, 0)),
+                &segment((2, -3, 0), (2, 5, 0))
+            )
+        );
+        assert_eq!(
+            Some(((2, 2, 0), (4, 4, 0))),
+            segment_crossing(
+                &segment((0, 0, 0), (4, 4, 0)),
+                &segment((7, 7, 0), (2, 2, 0))
+            )
+        );
+        assert_eq!(
+            None,
+            segment_crossing(
+                &segment((0, 0, 0), (4, 4, 0)),
+                &segment((1, 0, 0), (5, 4, 0))
+            )
+        );
+        // Lines in different planes pass each other without meeting
+        assert_eq!(
+            None,
+            segment_crossing(
+                &segment((0, 0, 0), (4, 0, 0)),
+                &segment((2, -2, 1), (2, 2, 1))
+            )
+        );
+        assert_eq!(
+            Some(((2, 0, 0), (2, 0, 0))),
+            segment_crossing(
+                &segment((0, 0, 0), (4, 0, 0)),
+                &segment((2, 0, -3), (2, 0, 3))
+            )
+        );
+        assert_eq!(
+            Some(((1, 1, 1), (1, 1, 1))),
+            segment_crossing(
+                &segment((0, 0, 0), (3, 3, 3)),
+                &segment((2, 0, 0), (0, 2, 2))
+            )
+        );
+
+        // Steps and gaps too big to multiply together in an i64
+        let (x, y) = (3_000_000_000_000_000_001, 3_000_000_000_000_000_000);
+        assert_eq!(
+            Some(((x, y, 0), (x, y, 0))),
+            segment_crossing(
+                &segment((0, 0, 0), (2 * x, 2 * y, 0)),
+                &segment((2 * x, 0, 0), (0, 2 * y, 0))
+            )
+        );
+        assert_eq!(
+            None,
+            segment_crossing(
+                &segment((0, 0, 0), (x, y, 0)),
+                &segment((x, 0, 0), (0, y, 0))
+            )
+        );
+        assert_eq!(
+            None,
+            segment_crossing(
+                &segment((i64::MIN, 0, 0), (i64::MIN + 1, 1, 0)),
+                &segment((i64::MAX, 0, 0), (i64::MAX - 1, 1, 0))
+            )
+        );
+        assert_eq!(
+            None,
+            segment_crossing(
+                &segment((i64::MIN, 0, 0), (i64::MIN + 1, 0, 0)),
+                &segment((i64::MAX - 1, 0, 0), (i64::MAX, 0, 0))
+            )
+        );
+    }
+
+    #[test]
+    fn test_crossings() {
+        let wire1 = get_wire("R8,U5,L5,D3").unwrap();
+        let wire2 = get_wire("U7,R6,D4,L4").unwrap();
+        let mut points: Vec<Coord> = crossings(&wire1, &wire2)
+            .iter()
+            .map(|crossing| crossing.from)
+            .collect();
+        points.sort_unstable();
+        assert_eq!(vec![(0, 0, 0), (3, 3, 0), (6, 5, 0)], points);
+
+        // Wires running over each other share every point of the overlap
+        let wire1 = get_wire("R10").unwrap();
+        let wire2 = get_wire("U1,R2,D1,R4,L1").unwrap();
+        let mut runs: Vec<(Coord, Coord)> = crossings(&wire1, &wire2)
+            .iter()
+            .map(|crossing| (crossing.from, crossing.to))
+            .collect();
+        runs.sort_unstable();
+        assert_eq!(
+            vec![
+                ((0, 0, 0), (0, 0, 0)),
+                ((2, 0, 0), (2, 0, 0)),
+                ((2, 0, 0), (6, 0, 0)),
+                ((5, 0, 0), (6, 0, 0))
+            ],
+            runs
+        );
+    }
+
+    #[test]
+    fn test_candidates() {
+        assert_eq!(
+            vec![(-1, 0, 0), (0, 0, 0), (1, 0, 0)],
+            nearest(&Manhattan, (0, 0, 0), (-3, 0, 0), (1, 0, 0), 6)
+        );
+        assert_eq!(vec![(2, 4, 0), (2, 9, 0)], ends((2, 4, 0), (0, 1, 0), 5));
+    }
+
+    // The closest intersections found from segments must match those found by visiting every
+    // point of both wires.
+    #[test]
+    fn test_matches_point_by_point() {
+        let cases = [
+            ("R8,U5,L5,D3", "U7,R6,D4,L4"),
+            ("R10", "U1,R2,D1,R4,L1"),
+            ("R5,U5,L5,D10,R3", "L2,U3,R9,D6,L4,U1"),
+            ("U3,R3,D6,L6,U6,R6", "R1,U1,R1,D2,L3"),
+            ("L4,D4,R8,U8,L1", "D1,L5,U9,R2,D20"),
+            ("NE4,SE4,L8", "U2,R6,D4"),
+            ("R5,NW5,D5", "NE3,SW6"),
+            ("V3,-2,V3,2,L6", "R3,D4,NE2"),
+            ("SW3,NE6", "NE5,SE1"),
+            ("SE3,NE3,NW3,SW3", "R6,U1,L6"),
+            ("NE1", "R1,NW1,V4,2"),
+            ("R3,F2,L3,B2", "F1,R5"),
+            ("F2,R4,B4,U2", "R2,F3,B6,U1"),
+            ("V2,2,2,R3", "F1,NE3,B1,V1,0,2"),
+        ];
+        for (line1, line2) in cases.iter() {
+            let path1 = get_path(line1).unwrap();
+            let path2 = get_path(line2).unwrap();
+            let coords1: HashSet<Coord> = path1.iter().cloned().collect();
+            let coords2: HashSet<Coord> = path2.iter().cloned().collect();
+            let common: Vec<&Coord> = coords1
+                .intersection(&coords2)
+                .filter(|&&coord| coord != (0, 0, 0))
+                .collect();
+            let by_manhattan = common
+                .iter()
+                .map(|&&coord| Manhattan.distance((0, 0, 0), coord))
+                .min();
+            let by_steps = common
+                .iter()
+                .map(|coord| {
+                    let dist1 = path1.iter().position(|c| c == *coord).unwrap();
+                    let dist2 = path2.iter().position(|c| c == *coord).unwrap();
+                    (dist1 + dist2) as i128
+                })
+                .min();
+
+            let wire1 = get_wire(line1).unwrap();
+            let wire2 = get_wire(line2).unwrap();
+            assert_eq!(
+                by_manhattan,
+                closest_intersection_by_manhattan(&wire1, &wire2)
+            );
+            assert_eq!(by_steps, closest_intersection_by_steps(&wire1, &wire2));
+        }
+    }
+
+    #[test]
+    fn test_closest_intersection_by_manhattan() {
+        let path1 = get_wire("R1").unwrap();
+        let path2 = get_wire("U1").unwrap();
+        assert_eq!(None, closest_intersection_by_manhattan(&path1, &path2));
+
+        let path1 = get_wire("R1").unwrap();
+        let path2 = get_wire("U1,R1,D1").unwrap();
+        assert_eq!(Some(1), closest_intersection_by_manhattan(&path1, &path2));
+
+        let path1 = get_wire("R8,U5,L5,D3").unwrap();
+        let path2 = get_wire("U7,R6,D4,L4").unwrap();
+        assert_eq!(Some(6), closest_intersection_by_manhattan(&path1, &path2));
+
+        let path1 = get_wire("R75,D30,R83,U83,L12,D49,R71,U7,L72").unwrap();
+        let path2 = get_wire("U62,R66,U55,R34,D71,R55,D58,R83").unwrap();
+        assert_eq!(Some(159), closest_intersection_by_manhattan(&path1, &path2));
+
+        let path1 = get_wire("R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51").unwrap();
+        let path2 = get_wire("U98,R91,D20,R16,D67,R40,U7,R15,U6,R7").unwrap();
+        assert_eq!(Some(135), closest_intersection_by_manhattan(&path1, &path2));
+    }
+
+    #[test]
+    fn test_long_wires() {
+        let wire1 = get_wire("R4000000000,U3000000000").unwrap();
+        let wire2 = get_wire("U1000000000,R5000000000").unwrap();
+        assert_eq!(
+            Some(5_000_000_000),
+            closest_intersection_by_manhattan(&wire1, &wire2)
+        );
+        assert_eq!(
+            Some(10_000_000_000),
+            closest_intersection_by_steps(&wire1, &wire2)
+        );
+    }
+
+    #[test]
+    fn test_shared_points() {
+        let wires = vec![
+            get_wire("R8,U5,L5,D3").unwrap(),
+            get_wire("U7,R6,D4,L4").unwrap(),
+            get_wire("U3,R6").unwrap(),
+        ];
+        let runs = shared_runs(&wires);
+        let points = shared_points(&wires);
+
+        // The third wire runs over parts of the second, so they share every point of those runs
+        let expected: Vec<(Coord, Vec<(usize, i64)>)> = vec![
+            ((0, 1, 0), vec![(1, 1), (2, 1)]),
+            ((0, 2, 0), vec![(1, 2), (2, 2)]),
+            ((0, 3, 0), vec![(1, 3), (2, 3)]),
+            ((2, 3, 0), vec![(1, 21), (2, 5)]),
+            ((3, 3, 0), vec![(0, 20), (1, 20), (2, 6)]),
+            ((4, 3, 0), vec![(1, 19), (2, 7)]),
+            ((5, 3, 0), vec![(1, 18), (2, 8)]),
+            ((6, 3, 0), vec![(1, 17), (2, 9)]),
+            ((6, 5, 0), vec![(0, 15), (1, 15)]),
+        ];
+        let actual: Vec<(Coord, Vec<(usize, i64)>)> = points
+            .iter()
+            .map(|(&coord, wires_here)| (coord, wires_here.clone().into_iter().collect()))
+            .collect();
+        assert_eq!(expected, actual);
+
+        assert_eq!(Some(((0, 1, 0), 1)), closest_shared_by_manhattan(&runs, 2));
+        assert_eq!(Some(((0, 1, 0), 2)), closest_shared_by_steps(&runs, 2));
+        assert_eq!(Some(((3, 3, 0), 6)), closest_shared_by_manhattan(&runs, 3));
+        assert_eq!(Some(((3, 3, 0), 46)), closest_shared_by_steps(&runs, 3));
+        assert_eq!(None, closest_shared_by_steps(&runs, 4));
+
+        // The overlaps are kept as runs, cut where the wires cross, turn off or meet a third wire
+        let ends: Vec<(Coord, Coord)> = runs.iter().map(|run| (run.from, run.to())).collect();
+        assert_eq!(
+            vec![
+                ((0, 1, 0), (0, 2, 0)),
+                ((0, 3, 0), (0, 3, 0)),
+                ((2, 3, 0), (2, 3, 0)),
+                ((3, 3, 0), (3, 3, 0)),
+                ((4, 3, 0), (5, 3, 0)),
+                ((6, 3, 0), (6, 3, 0)),
+                ((6, 5, 0), (6, 5, 0)),
+            ],
+            ends
+        );
+        assert_eq!(9, count_shared(&runs, 2));
+        assert_eq!(1, count_shared(&runs, 3));
+    }
+
+    #[test]
+    fn test_long_overlaps() {
+        let wires = vec![
+            get_wire("R1000000000").unwrap(),
+            get_wire("U1,R5,D1,R1000000000").unwrap(),
+            get_wire("L1,R1000000001").unwrap(),
+        ];
+        let runs = shared_runs(&wires);
+        assert_eq!(1_000_000_000, count_shared(&runs, 2));
+        assert_eq!(1_000_000_000 - 4, count_shared(&runs, 3));
+        assert_eq!(Some(((1, 0, 0), 1)), closest_shared_by_manhattan(&runs, 2));
+        assert_eq!(
+            Some(((5, 0, 0), 5 + 7 + 7)),
+            closest_shared_by_steps(&runs, 3)
+        );
+
+        // Wires that each take more than half the steps an i64 can hold to get there
+        let wires = vec![
+            get_wire("U1,R9000000000000000000,D2").unwrap(),
+            get_wire("D1,R9000000000000000000,U2").unwrap(),
+        ];
+        assert_eq!(
+            Some((
+                (9_000_000_000_000_000_000, -1, 0),
+                18_000_000_000_000_000_004
+            )),
+            closest_shared_by_steps(&shared_runs(&wires), 2)
+        );
+    }
+
+    #[test]
+    fn test_pairs() {
+        let wires = vec![vec![]; 3];
+        assert_eq!(
+            vec![(0, 1), (0, 2), (1, 2)],
+            pairs(&wires).collect::<Vec<_>>()
+        );
+        assert_eq!(0, pairs(&wires[..1]).count());
+    }
+
+    #[test]
+    fn test_closest_intersection_by_steps() {
+        let path1 = get_wire("R1").unwrap();
+        let path2 = get_wire("U1").unwrap();
+        assert_eq!(None, closest_intersection_by_steps(&path1, &path2));
+
+        let path1 = get_wire("R8,U5,L5,D3").unwrap();
+        let path2 = get_wire("U7,R6,D4,L4").unwrap();
+        assert_eq!(Some(30), closest_intersection_by_steps(&path1, &path2));
+
+        let path1 = get_wire("R75,D30,R83,U83,L12,D49,R71,U7,L72").unwrap();
+        let path2 = get_wire("U62,R66,U55,R34,D71,R55,D58,R83").unwrap();
+        assert_eq!(Some(610), closest_intersection_by_steps(&path1, &path2));
+
+        let path1 = get_wire("R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51").unwrap();
+        let path2 = get_wire("U98,R91,D20,R16,D67,R40,U7,R15,U6,R7").unwrap();
+        assert_eq!(Some(410), closest_intersection_by_steps(&path1, &path2));
+
+        // Both wires take more than half the steps an i64 can hold to get there
+        let path1 = get_wire("U1,R9000000000000000000,D2").unwrap();
+        let path2 = get_wire("D1,R9000000000000000000,U2").unwrap();
+        assert_eq!(
+            Some(18_000_000_000_000_000_004),
+            closest_intersection_by_steps(&path1, &path2)
+        );
+    }
+}
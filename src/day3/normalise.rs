@@ -0,0 +1,182 @@
+use super::{
+    advance, parse_lines, parse_tokens, print_errors, Coord, Error, Instruction, ParseError,
+    Segment,
+};
+use std::fs;
+
+// Rewrites every wire in canonical form, so wire files written by different tools can be compared,
+// and writes them to `output`, or prints them if no file is given.
+pub fn export(input: &str, output: Option<&str>) {
+    let lines = match parse_lines(input, |line| normalise(&parse_tokens(line)?)) {
+        Ok(lines) => lines,
+        Err(errors) => {
+            print_errors(&errors);
+            return;
+        }
+    };
+    let mut text = String::new();
+    for moves in lines.iter() {
+        text.push_str(&to_text(moves));
+        text.push('\n');
+    }
+
+    match output {
+        Some(path) => match fs::write(path, text) {
+            Ok(()) => println!("Wrote {} wires to {}", lines.len(), path),
+            Err(why) => println!("Couldn't write {}: {}", path, why),
+        },
+        None => print!("{}", text),
+    }
+}
+
+// Reduces instructions to the fewest moves that trace the same path without doubling back. Moves
+// that go nowhere are dropped, moves in the same direction are joined, and a move back the way
+// the wire came cancels as much of the last move as it can, which may bring the moves either side
+// of it together. Joining moves that would then go further than a coordinate can hold is an
+// error, given on line 1 for the caller to move.
+fn normalise(
+    tokens: &[(usize, String, Instruction)],
+) -> Result<Vec<(i64, Coord)>, Vec<ParseError>> {
+    let mut moves: Vec<(i64, Coord)> = vec![];
+    for (token, text, instruction) in tokens {
+        let (mut dist, step) = instruction.steps();
+        if dist <= 0 {
+            continue;
+        }
+        let back = (-step.0, -step.1, -step.2);
+        loop {
+            match moves.last_mut() {
+                Some((last, last_step)) if *last_step == step => {
+                    let joined = last.checked_add(dist).filter(|&joined| fits(step, joined));
+                    match joined {
+                        Some(joined) => *last = joined,
+                        None => {
+                            return Err(vec![ParseError {
+                                line: 1,
+                                token: *token,
+                                text: text.clone(),
+                                error: Error::DistanceOverflow,
+                            }])
+                        }
+                    }
+                    break;
+                }
+                Some((last, last_step)) if *last_step == back => {
+                    if *last > dist {
+                        *last -= dist;
+                        break;
+                    }
+                    dist -= *last;
+                    moves.pop();
+                    if dist == 0 {
+                        break;
+                    }
+                }
+                _ => {
+                    moves.push((dist, step));
+                    break;
+                }
+            }
+        }
+    }
+    Ok(moves)
+}
+
+// Whether `dist` steps of `step` can be written as a single move
+fn fits((dx, dy, dz): Coord, dist: i64) -> bool {
+    dx.checked_mul(dist).is_some()
+        && dy.checked_mul(dist).is_some()
+        && dz.checked_mul(dist).is_some()
+}
+
+// Writes moves as instructions. Vector moves are written out in full, as a single step of them
+// would need repeating.
+fn to_text(moves: &[(i64, Coord)]) -> String {
+    let instructions: Vec<String> = moves
+        .iter()
+        .map(|&(dist, step)| {
+            let segment = Segment {
+                start: (0, 0, 0),
+                end: advance((0, 0, 0), step, dist),
+                steps: 0,
+            };
+            match segment.direction() {
+                direction if direction.starts_with('V') => match segment.end {
+                    (x, y, 0) => format!("V{},{}", x, y),
+                    (x, y, z) => format!("V{},{},{}", x, y, z),
+                },
+                direction => format!("{}{}", direction, dist),
+            }
+        })
+        .collect();
+    instructions.join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day3::get_wire;
+
+    fn normalised(line: &str) -> String {
+        to_text(&normalise(&parse_tokens(line).unwrap()).unwrap())
+    }
+
+    #[test]
+    fn test_normalise() {
+        assert_eq!("R7", normalised("R3,R4"));
+        assert_eq!("R8,U5,L5,D3", normalised("R8,U5,L5,D3"));
+        assert_eq!("R2", normalised("R2,U5,D5"));
+        assert_eq!("", normalised("U5,D5"));
+        assert_eq!("R2,U1", normalised("R2,U0,U1"));
+        // Cancelling a move lets the moves either side of it join
+        assert_eq!("R4", normalised("R3,U2,D2,R1"));
+        // Going back further than the last move carries on into the one before
+        assert_eq!("R1,D3", normalised("R3,U2,D2,L2,D3"));
+        assert_eq!("D2", normalised("U3,D5"));
+        // Other ways of writing the same move come out the same
+        assert_eq!("NE5,F3", normalised("V2,2,NE3,Z+1,F2"));
+        assert_eq!("V6,2,B1", normalised("V3,1,V3,1,Z-1"));
+        assert_eq!("V2,-1,3", normalised("V2,-1,3,V-2,1,-3,V2,-1,3"));
+    }
+
+    #[test]
+    fn test_too_long() {
+        let tokens = parse_tokens("R9000000000000000000,R9000000000000000000").unwrap();
+        assert_eq!(
+            Err(vec![ParseError {
+                line: 1,
+                token: 2,
+                text: "R9000000000000000000".to_string(),
+                error: Error::DistanceOverflow,
+            }]),
+            normalise(&tokens)
+        );
+        // Vectors are written out in full, so each distance has to fit once joined
+        let tokens = parse_tokens("V4611686018427387904,2,V4611686018427387904,2").unwrap();
+        assert!(normalise(&tokens).is_err());
+    }
+
+    // A normalised wire passes through the same points in the same order as the original, apart
+    // from where the original doubled back
+    #[test]
+    fn test_same_points() {
+        for line in [
+            "R8,U5,L5,D3",
+            "R3,R4,U0,NE2,V2,1,F1",
+            "R75,D30,R83,U83,L12,D49",
+        ]
+        .iter()
+        {
+            let canonical = normalised(line);
+            let ends = |line: &str| -> Vec<Coord> {
+                get_wire(line).unwrap().iter().map(|s| s.end).collect()
+            };
+            assert_eq!(ends(line).last(), ends(&canonical).last());
+            assert_eq!(canonical, normalised(&canonical));
+        }
+        assert_eq!(
+            get_wire("R8,U5,L5,D3").unwrap(),
+            get_wire(&normalised("R3,R5,U5,L5,D3")).unwrap()
+        );
+    }
+}
@@ -0,0 +1,294 @@
+use super::index::WireIndex;
+use super::{get_paths, Coord, Wire};
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, BinaryHeap, HashMap};
+
+// Up, down, left and right, in the order the instructions name them
+const DIRECTIONS: [(&str, i64, i64); 4] = [("U", 0, 1), ("D", 0, -1), ("L", -1, 0), ("R", 1, 0)];
+
+// A point on the compressed grid and the direction taken to reach it, with one extra direction
+// for the start
+type State = ((usize, usize), usize);
+
+#[derive(Debug, PartialEq)]
+struct Route {
+    instructions: String,
+    length: i64,
+    turns: i64,
+}
+
+// Suggests a new path for the second wire, to the same end point, that never meets the first.
+// Each turn costs `turn_penalty` extra steps, so a higher penalty favours simpler routes over
+// shorter ones.
+pub fn report(input: &str, turn_penalty: i64) {
+    let wires = match get_paths(input) {
+        None => return,
+        Some(wires) => wires,
+    };
+
+    let end = wires[1].last().map_or((0, 0, 0), |last| last.end);
+    if end.2 != 0 {
+        println!("Only wires that end on the layer they start on can be rerouted");
+        return;
+    }
+    let before = wires[1].last().map_or(0, |last| last.steps + last.length());
+    match reroute(&wires[0], end, turn_penalty) {
+        Some(route) => {
+            println!(
+                "Rerouted wire 2 is {} steps long with {} turns, was {} steps:",
+                route.length, route.turns, before
+            );
+            println!("{}", route.instructions);
+        }
+        None => println!("Wire 2 can't reach {:?} without crossing wire 1", end),
+    }
+}
+
+// Finds the cheapest path in the starting layer from the origin to `end` that avoids every point
+// of `blocker`. Only rows and columns next to where the blocking wire turns or gets in the way can
+// hold a turn on a best path, so A* searches a grid compressed down to those, and the lines
+// through the end. The grid is only filled in as far as the search reaches.
+fn reroute(blocker: &Wire, end: Coord, turn_penalty: i64) -> Option<Route> {
+    let index = WireIndex::new(vec![blocker.clone()]);
+    if end != (0, 0, 0) && !index.wires_at(end).is_empty() {
+        return None;
+    }
+
+    let (xs, ys) = grid_lines(blocker, end);
+    let position = |values: &[i64], v: i64| values.binary_search(&v).unwrap();
+    let start = (position(&xs, 0), position(&ys, 0));
+    let goal = (position(&xs, end.0), position(&ys, end.1));
+    let heuristic = |(i, j): (usize, usize)| (xs[i] - end.0).abs() + (ys[j] - end.1).abs();
+
+    // Ties in cost go to the route with fewer turns
+    let mut best: HashMap<State, (i64, i64)> = HashMap::new();
+    let mut came_from: HashMap<State, State> = HashMap::new();
+    let mut queue = BinaryHeap::new();
+    best.insert((start, 4), (0, 0));
+    queue.push(Reverse((heuristic(start), 0, 0, start, 4)));
+
+    while let Some(Reverse((_, cost, turns, at, d))) = queue.pop() {
+        if (cost, turns) > best[&(at, d)] {
+            continue;
+        }
+        if at == goal {
+            return Some(route(&xs, &ys, &came_from, (at, d)));
+        }
+        for (next_d, &(_, dx, dy)) in DIRECTIONS.iter().enumerate() {
+            let next = match neighbour(at, dx, dy, xs.len(), ys.len()) {
+                Some(next) => next,
+                None => continue,
+            };
+            // A move is blocked if the other wire touches any point of it past where it starts.
+            // Nothing is gained by going back to the origin, where the other wire starts too.
+            let (x1, y1) = (xs[at.0] + dx, ys[at.1] + dy);
+            let (x2, y2) = (xs[next.0], ys[next.1]);
+            let (min, max) = ((x1.min(x2), y1.min(y2), 0), (x1.max(x2), y1.max(y2), 0));
+            if next == start || !index.segments_in(min, max).is_empty() {
+                continue;
+            }
+            let turn = (d != 4 && d != next_d) as i64;
+            let length = (xs[next.0] - xs[at.0]).abs() + (ys[next.1] - ys[at.1]).abs();
+            let next_cost = (cost + length + turn * turn_penalty, turns + turn);
+            if best
+                .get(&(next, next_d))
+                .is_none_or(|&known| next_cost < known)
+            {
+                best.insert((next, next_d), next_cost);
+                came_from.insert((next, next_d), (at, d));
+                queue.push(Reverse((
+                    next_cost.0 + heuristic(next),
+                    next_cost.0,
+                    next_cost.1,
+                    next,
+                    next_d,
+                )));
+            }
+        }
+    }
+    None
+}
+
+fn neighbour(
+    (i, j): (usize, usize),
+    dx: i64,
+    dy: i64,
+    width: usize,
+    height: usize,
+) -> Option<(usize, usize)> {
+    let i = i as i64 + dx;
+    let j = j as i64 + dy;
+    if i < 0 || j < 0 || i >= width as i64 || j >= height as i64 {
+        return None;
+    }
+    Some((i as usize, j as usize))
+}
+
+// Walks back from the goal, joining moves in the same direction into one instruction
+fn route(xs: &[i64], ys: &[i64], came_from: &HashMap<State, State>, goal: State) -> Route {
+    let coord = |((i, j), _): State| (xs[i], ys[j]);
+    let mut moves: Vec<(usize, i64)> = vec![];
+    let mut at = goal;
+    while let Some(&from) = came_from.get(&at) {
+        let ((x1, y1), (x2, y2)) = (coord(from), coord(at));
+        let distance = (x2 - x1).abs() + (y2 - y1).abs();
+        match moves.last_mut() {
+            Some((d, total)) if *d == at.1 => *total += distance,
+            _ => moves.push((at.1, distance)),
+        }
+        at = from;
+    }
+    moves.reverse();
+
+    let instructions: Vec<String> = moves
+        .iter()
+        .map(|&(d, distance)| format!("{}{}", DIRECTIONS[d].0, distance))
+        .collect();
+    let length = moves.iter().map(|&(_, distance)| distance).sum();
+    let turns = (moves.len() as i64 - 1).max(0);
+    Route {
+        instructions: instructions.join(","),
+        length,
+        turns,
+    }
+}
+
+// The columns and rows a best path might have to turn on, in order. Those are either side of the
+// ends of the blocking wire's moves in the starting layer, and of the origin and the end. A
+// slanted move could be passed between any two of its points, but a path only has to turn beside
+// one where the move blocks a row or column it could take, so only those points are added.
+fn grid_lines(wire: &Wire, end: Coord) -> (Vec<i64>, Vec<i64>) {
+    let mut points = vec![(0, 0), (end.0, end.1)];
+    let mut slanted = vec![];
+    for segment in wire.iter() {
+        let (start, step, length) = (segment.start, segment.step(), segment.length());
+        if step.2 != 0 {
+            // A move between layers passes through the starting layer once at most
+            if let Some(k) = reaches(start.2, step.2, length, 0) {
+                let (x, y, _) = segment.at(k);
+                points.push((x, y));
+            }
+        } else if start.2 == 0 {
+            points.extend([start, segment.end].iter().map(|&(x, y, _)| (x, y)));
+            if step.0 != 0 && step.1 != 0 {
+                slanted.push(segment);
+            }
+        }
+    }
+
+    let beside = |lines: &mut BTreeSet<i64>, v: i64| {
+        lines.extend(&[v.saturating_sub(1), v, v.saturating_add(1)]);
+    };
+    let (mut xs, mut ys) = (BTreeSet::new(), BTreeSet::new());
+    for &(x, y) in points.iter() {
+        beside(&mut xs, x);
+        beside(&mut ys, y);
+    }
+    let (columns, rows) = (xs.clone(), ys.clone());
+    for segment in slanted {
+        let (start, step, length) = (segment.start, segment.step(), segment.length());
+        for &y in rows.iter() {
+            if let Some(k) = reaches(start.1, step.1, length, y) {
+                beside(&mut xs, segment.at(k).0);
+            }
+        }
+        for &x in columns.iter() {
+            if let Some(k) = reaches(start.0, step.0, length, x) {
+                beside(&mut ys, segment.at(k).1);
+            }
+        }
+    }
+    (xs.into_iter().collect(), ys.into_iter().collect())
+}
+
+// Which of the `length` steps of `step` on from `from`, along one axis, lands on `to`, if any
+fn reaches(from: i64, step: i64, length: i64, to: i64) -> Option<i64> {
+    let gap = to as i128 - from as i128;
+    if step == 0 || gap % step as i128 != 0 {
+        return None;
+    }
+    let k = gap / step as i128;
+    if k >= 0 && k <= length as i128 {
+        Some(k as i64)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day3::{get_wire, shared_points, shared_runs};
+
+    // The rerouted wire has to parse, end in the same place and cross nothing
+    fn check(blocker: &str, route: &Route, end: Coord) {
+        let wires = vec![
+            get_wire(blocker).unwrap(),
+            get_wire(&route.instructions).unwrap(),
+        ];
+        assert_eq!(Some(end), wires[1].last().map(|last| last.end));
+        assert!(shared_points(&wires).is_empty());
+        let length = wires[1].last().map_or(0, |last| last.steps + last.length());
+        assert_eq!(route.length, length);
+    }
+
+    #[test]
+    fn test_reroute() {
+        // The first example already avoids the first wire if it goes straight there
+        let route = reroute(&get_wire("R8,U5,L5,D3").unwrap(), (2, 3, 0), 0).unwrap();
+        assert_eq!(5, route.length);
+        check("R8,U5,L5,D3", &route, (2, 3, 0));
+
+        // A wall in the way is shortest to pass underneath
+        let route = reroute(&get_wire("L1,U3,R5").unwrap(), (0, 5, 0), 0).unwrap();
+        assert_eq!(11, route.length);
+        assert_eq!(3, route.turns);
+        check("L1,U3,R5", &route, (0, 5, 0));
+
+        // But has fewer turns going round the far end
+        let route = reroute(&get_wire("L1,U3,R5").unwrap(), (0, 5, 0), 10).unwrap();
+        assert_eq!("R5,U5,L5", route.instructions);
+        assert_eq!(2, route.turns);
+
+        // Slanted moves can't be slipped through either
+        let route = reroute(&get_wire("L2,NE4,SE2").unwrap(), (0, 3, 0), 0).unwrap();
+        check("L2,NE4,SE2", &route, (0, 3, 0));
+
+        // A long wall is only looked up where the route goes, which is round its near end
+        let route = reroute(&get_wire("D1,R1000000000").unwrap(), (5, -2, 0), 0).unwrap();
+        assert_eq!(9, route.length);
+        assert_eq!(
+            Some((5, -2, 0)),
+            get_wire(&route.instructions).unwrap().last().map(|l| l.end)
+        );
+
+        // Long slanted moves and moves between layers only add the lines the search can use
+        let blocker = get_wire("L1,NE100000000").unwrap();
+        let route = reroute(&blocker, (5, 7, 0), 0).unwrap();
+        assert_eq!(18, route.length);
+        let wires = vec![blocker, get_wire(&route.instructions).unwrap()];
+        assert!(shared_runs(&wires).is_empty());
+        let route = reroute(&get_wire("F10000000000").unwrap(), (3, 0, 0), 0).unwrap();
+        assert_eq!("R3", route.instructions);
+    }
+
+    #[test]
+    fn test_grid_lines() {
+        let (xs, ys) = grid_lines(&get_wire("NE1000,F3").unwrap(), (0, 5, 0));
+        // The rows through the end cross the slanted move at x = 4, 5 and 6
+        assert_eq!(vec![-1, 0, 1, 2, 3, 4, 5, 6, 7, 998, 999, 1000, 1001], xs);
+        assert_eq!(vec![-1, 0, 1, 2, 4, 5, 6, 998, 999, 1000, 1001], ys);
+    }
+
+    #[test]
+    fn test_unreachable() {
+        // Boxed in
+        let blocker = get_wire("D1,R1,U2,L2,D2,R1").unwrap();
+        assert_eq!(None, reroute(&blocker, (5, 5, 0), 0));
+        // Ending on the other wire
+        assert_eq!(None, reroute(&get_wire("R8").unwrap(), (3, 0, 0), 0));
+        // Already there
+        let route = reroute(&blocker, (0, 0, 0), 0).unwrap();
+        assert_eq!("", route.instructions);
+    }
+}
@@ -0,0 +1,211 @@
+use super::{get_paths, shared_runs, Coord, SharedRun, Wire};
+use std::collections::HashMap;
+
+// Which visits count towards the steps to a point that a wire passes through more than once. The
+// puzzle counts the first, `Every` adds up the steps taken to reach it each time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Visit {
+    First,
+    Latest,
+    Every,
+}
+
+// Every point where a wire meets another, and the steps it takes to get there each time, in
+// order, so the first visit is first. It's worked out once from the segments the wire passes
+// through each run of shared points along, rather than by walking the whole wire.
+struct StepMap(HashMap<Coord, Vec<i64>>);
+
+impl StepMap {
+    fn new(wire: usize, runs: &[SharedRun]) -> StepMap {
+        let mut visits: HashMap<Coord, Vec<i64>> = HashMap::new();
+        for run in runs {
+            let segments = match run.segments.get(&wire) {
+                Some(segments) => segments,
+                None => continue,
+            };
+            for coord in run.points() {
+                let steps = visits.entry(coord).or_default();
+                steps.extend(segments.iter().map(|segment| segment.steps_to(coord)));
+                // Where one segment ends and the next starts the point is only reached once
+                steps.sort_unstable();
+                steps.dedup();
+            }
+        }
+        StepMap(visits)
+    }
+
+    fn visits(&self, coord: Coord) -> &[i64] {
+        self.0.get(&coord).map_or(&[], |visits| &visits[..])
+    }
+
+    // The steps to reach a point counting the chosen visits. Each visit's steps fit in an i64,
+    // but adding them all up might not.
+    fn steps(&self, coord: Coord, visit: Visit) -> Option<i128> {
+        let visits = self.visits(coord);
+        match visit {
+            Visit::First => visits.first().map(|&steps| steps as i128),
+            Visit::Latest => visits.last().map(|&steps| steps as i128),
+            Visit::Every if visits.is_empty() => None,
+            Visit::Every => Some(visits.iter().map(|&steps| steps as i128).sum()),
+        }
+    }
+}
+
+// An intersection, the steps each wire through it takes every time it gets there, and the total
+// steps counted by the chosen visits
+type Steps = (Coord, Vec<(usize, Vec<i64>)>, i128);
+
+// Lists every intersection with the steps each wire takes to reach it, then the closest one
+// counting steps by `visit`.
+pub fn report(input: &str, visit: Visit) {
+    let wires = match get_paths(input) {
+        None => return,
+        Some(wires) => wires,
+    };
+
+    let found = intersection_steps(&wires, visit);
+    for (coord, visits, total) in found.iter() {
+        let per_wire: Vec<String> = visits
+            .iter()
+            .map(|(wire, steps)| {
+                let steps: Vec<String> = steps.iter().map(|s| s.to_string()).collect();
+                format!("wire {} at {}", wire + 1, steps.join(", "))
+            })
+            .collect();
+        println!("{:?}: {} -> {} steps", coord, per_wire.join("; "), total);
+    }
+
+    match found
+        .iter()
+        .min_by_key(|&&(coord, _, total)| (total, coord))
+    {
+        Some((coord, _, total)) => println!(
+            "The closest intersection counting {} visits is {:?} at {} steps",
+            format!("{:?}", visit).to_lowercase(),
+            coord,
+            total
+        ),
+        None => println!("There are no intersections"),
+    }
+}
+
+// Finds the intersections with the sweep, then looks up the steps to each in every wire's step
+// map instead of walking the wires again. Every point of an overlap is listed.
+fn intersection_steps(wires: &[Wire], visit: Visit) -> Vec<Steps> {
+    let runs = shared_runs(wires);
+    let maps: Vec<StepMap> = (0..wires.len())
+        .map(|wire| StepMap::new(wire, &runs))
+        .collect();
+
+    let mut found: Vec<Steps> = runs
+        .iter()
+        .flat_map(|run| run.points().map(move |coord| (coord, run)))
+        .map(|(coord, run)| {
+            let visits: Vec<(usize, Vec<i64>)> = run
+                .segments
+                .keys()
+                .map(|&wire| (wire, maps[wire].visits(coord).to_vec()))
+                .collect();
+            let total = run
+                .segments
+                .keys()
+                .filter_map(|&wire| maps[wire].steps(coord, visit))
+                .sum();
+            (coord, visits, total)
+        })
+        .collect();
+    found.sort_unstable_by_key(|&(coord, _, _)| coord);
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day3::{closest_intersection_by_steps, get_wire};
+
+    #[test]
+    fn test_step_map() {
+        let wires = vec![
+            get_wire("R5,U2,L2,D4").unwrap(),
+            get_wire("U1,R3,D3").unwrap(),
+        ];
+        let map = StepMap::new(0, &shared_runs(&wires));
+        assert_eq!(&[3, 11], map.visits((3, 0, 0)));
+        assert_eq!(&[12], map.visits((3, -1, 0)));
+        assert_eq!(&[] as &[i64], map.visits((4, 1, 0)));
+        assert_eq!(Some(3), map.steps((3, 0, 0), Visit::First));
+        assert_eq!(Some(11), map.steps((3, 0, 0), Visit::Latest));
+        assert_eq!(Some(14), map.steps((3, 0, 0), Visit::Every));
+        assert_eq!(None, map.steps((4, 1, 0), Visit::Every));
+    }
+
+    #[test]
+    fn test_intersection_steps() {
+        let wires = vec![
+            get_wire("R8,U5,L5,D3").unwrap(),
+            get_wire("U7,R6,D4,L4").unwrap(),
+        ];
+        assert_eq!(
+            vec![
+                ((3, 3, 0), vec![(0, vec![20]), (1, vec![20])], 40),
+                ((6, 5, 0), vec![(0, vec![15]), (1, vec![15])], 30),
+            ],
+            intersection_steps(&wires, Visit::First)
+        );
+
+        // The first wire passes (3, 0, 0) twice
+        let wires = vec![
+            get_wire("R5,U2,L2,D4").unwrap(),
+            get_wire("U1,R3,D3").unwrap(),
+        ];
+        let found = intersection_steps(&wires, Visit::Latest);
+        assert_eq!(
+            ((3, 0, 0), vec![(0, vec![3, 11]), (1, vec![5])], 16),
+            found[2]
+        );
+        let found = intersection_steps(&wires, Visit::Every);
+        assert_eq!(19, found[2].2);
+
+        // Every point of an overlap is listed
+        let wires = vec![get_wire("R4").unwrap(), get_wire("L1,R3").unwrap()];
+        let found = intersection_steps(&wires, Visit::First);
+        assert_eq!(
+            vec![
+                ((1, 0, 0), vec![(0, vec![1]), (1, vec![3])], 4),
+                ((2, 0, 0), vec![(0, vec![2]), (1, vec![4])], 6),
+            ],
+            found
+        );
+
+        // Both wires take more than half the steps an i64 can hold to get there
+        let wires = vec![
+            get_wire("U1,R9000000000000000000,D2").unwrap(),
+            get_wire("D1,R9000000000000000000,U2").unwrap(),
+        ];
+        let found = intersection_steps(&wires, Visit::Every);
+        assert_eq!(3, found.len());
+        assert_eq!(18_000_000_000_000_000_004, found[1].2);
+    }
+
+    #[test]
+    fn test_matches_closest_by_steps() {
+        let examples = [
+            (
+                "R75,D30,R83,U83,L12,D49,R71,U7,L72",
+                "U62,R66,U55,R34,D71,R55,D58,R83",
+            ),
+            (
+                "R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51",
+                "U98,R91,D20,R16,D67,R40,U7,R15,U6,R7",
+            ),
+        ];
+        for (line1, line2) in examples.iter() {
+            let wires = vec![get_wire(line1).unwrap(), get_wire(line2).unwrap()];
+            let closest = intersection_steps(&wires, Visit::First)
+                .into_iter()
+                .map(|(_, _, total)| total)
+                .min();
+            assert_eq!(closest_intersection_by_steps(&wires[0], &wires[1]), closest);
+        }
+    }
+}
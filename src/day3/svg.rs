@@ -0,0 +1,380 @@
+use super::metric::{Distance, Manhattan};
+use super::{
+    closest_shared_by_manhattan, closest_shared_by_steps, get_paths, shared_runs, vertices, Coord,
+    SharedRun, Wire,
+};
+use std::fmt::Write;
+use std::fs;
+
+const COLOURS: [&str; 8] = [
+    "#1f77b4", "#d62728", "#2ca02c", "#9467bd", "#ff7f0e", "#17becf", "#8c564b", "#e377c2",
+];
+
+// Draws every wire as a polyline with the origin and intersections marked, and writes the SVG to
+// `output`, or prints it if no file is given.
+pub fn render(input: &str, output: Option<&str>) {
+    let wires = match get_paths(input) {
+        None => return,
+        Some(wires) => wires,
+    };
+
+    let svg = to_svg(&wires);
+    match output {
+        Some(path) => match fs::write(path, svg) {
+            Ok(()) => println!("Wrote {}", path),
+            Err(why) => println!("Couldn't write {}: {}", path, why),
+        },
+        None => print!("{}", svg),
+    }
+}
+
+fn to_svg(wires: &[Wire]) -> String {
+    let paths: Vec<Vec<Coord>> = wires.iter().map(vertices).collect();
+    let runs = shared_runs(wires);
+    let mut closest: Vec<Coord> = vec![];
+    closest.extend(closest_shared_by_manhattan(&runs, 2).map(|(coord, _)| coord));
+    closest.extend(closest_shared_by_steps(&runs, 2).map(|(coord, _)| coord));
+    closest.dedup();
+
+    let (mut svg, unit) = open(&paths);
+
+    for (i, path) in paths.iter().enumerate() {
+        let coords: Vec<String> = path
+            .iter()
+            .map(|&(x, y, _)| format!("{},{}", x, flip(y)))
+            .collect();
+        writeln!(
+            svg,
+            r#"<polyline class="wire" data-wire="{}" points="{}" fill="none" stroke="{}" stroke-width="{:.2}"/>"#,
+            i + 1,
+            coords.join(" "),
+            COLOURS[i % COLOURS.len()],
+            unit
+        )
+        .unwrap();
+    }
+
+    // Overlaps are drawn as a thick line along them, labelled at their start
+    for run in runs.iter() {
+        let (x, y, _) = run.from;
+        let dist = Manhattan.distance((0, 0, 0), run.from);
+        let steps = run.total_steps(run.from);
+        if run.length == 0 {
+            circle(&mut svg, run.from, unit, closest.contains(&run.from));
+        } else {
+            let (x2, y2, _) = run.to();
+            writeln!(
+                svg,
+                r#"<line class="overlap" x1="{}" y1="{}" x2="{}" y2="{}" stroke="black" stroke-width="{:.2}"/>"#,
+                x,
+                flip(y),
+                x2,
+                flip(y2),
+                unit * 3.0
+            )
+            .unwrap();
+        }
+        writeln!(
+            svg,
+            r#"<text x="{:.2}" y="{:.2}" font-size="{:.2}">d={} s={}</text>"#,
+            x as f64 + unit * 3.0,
+            -(y as f64) - unit * 3.0,
+            unit * 6.0,
+            dist,
+            steps
+        )
+        .unwrap();
+    }
+    // The closest points can be partway along an overlap
+    for &coord in closest.iter() {
+        if !runs.iter().any(|run| run.length == 0 && run.from == coord) {
+            circle(&mut svg, coord, unit, true);
+        }
+    }
+
+    writeln!(
+        svg,
+        r#"<circle class="origin" cx="0" cy="0" r="{:.2}" fill="none" stroke="black" stroke-width="{:.2}"/>"#,
+        unit * 4.0,
+        unit
+    )
+    .unwrap();
+    svg.push_str("</svg>\n");
+
+    svg
+}
+
+fn circle(svg: &mut String, (x, y, _): Coord, unit: f64, highlighted: bool) {
+    writeln!(
+        svg,
+        r#"<circle class="{}" cx="{}" cy="{}" r="{:.2}" fill="{}"/>"#,
+        if highlighted {
+            "closest"
+        } else {
+            "intersection"
+        },
+        x,
+        flip(y),
+        if highlighted { unit * 4.0 } else { unit * 2.0 },
+        if highlighted { "gold" } else { "black" }
+    )
+    .unwrap();
+}
+
+// Starts an SVG big enough for every point of the paths on a white background, and gives the size
+// to draw markers at. Every layer is drawn on top of each other.
+fn open(paths: &[Vec<Coord>]) -> (String, f64) {
+    // Wires can reach either end of an i64, so the extents are worked out wider
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (0i128, 0, 0, 0);
+    for &(x, y, _) in paths.iter().flatten() {
+        min_x = min_x.min(x as i128);
+        max_x = max_x.max(x as i128);
+        min_y = min_y.min(flip(y));
+        max_y = max_y.max(flip(y));
+    }
+    // Markers and labels are sized relative to the drawing so they stay visible at any scale
+    let unit = ((max_x - min_x).max(max_y - min_y) as f64 / 200.0).max(0.1);
+    let margin = unit * 10.0;
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{:.2} {:.2} {:.2} {:.2}">"#,
+        min_x as f64 - margin,
+        min_y as f64 - margin,
+        (max_x - min_x) as f64 + 2.0 * margin,
+        (max_y - min_y) as f64 + 2.0 * margin
+    )
+    .unwrap();
+    writeln!(
+        svg,
+        r#"<rect x="{:.2}" y="{:.2}" width="100%" height="100%" fill="white"/>"#,
+        min_x as f64 - margin,
+        min_y as f64 - margin
+    )
+    .unwrap();
+    (svg, unit)
+}
+
+// SVG's y axis points down, so every y is flipped to keep up as up. The lowest y has no opposite
+// in an i64.
+fn flip(y: i64) -> i128 {
+    -(y as i128)
+}
+
+// Writes an animated SVG of the wires being laid down together, a step at a time, taking
+// `duration` seconds in all, to `output`, or prints it if no file is given.
+pub fn animate(input: &str, duration: f64, output: Option<&str>) {
+    let wires = match get_paths(input) {
+        None => return,
+        Some(wires) => wires,
+    };
+
+    let svg = to_animated_svg(&wires, duration);
+    match output {
+        Some(path) => match fs::write(path, svg) {
+            Ok(()) => println!("Wrote {}", path),
+            Err(why) => println!("Couldn't write {}: {}", path, why),
+        },
+        None => print!("{}", svg),
+    }
+}
+
+// The steps until every wire through a run has reached every point of it. A wire can't be at two
+// points at once, so its passes along the run one way all reach each point before or all after
+// those the other way, and the steps to its first visits only grow or shrink along the run. The
+// last point reached is then one end or the other.
+fn last_reached(run: &SharedRun) -> i64 {
+    [run.from, run.to()]
+        .iter()
+        .filter_map(|&coord| run.steps_at(coord).values().max().cloned())
+        .max()
+        .unwrap_or(0)
+}
+
+// Every wire takes a step in each tick, so each segment grows from its start over the ticks for
+// its steps. An intersection is found once the last wire through it gets there, which is when it
+// flashes, labelled with the steps the wires took to reach it, the sum part 2 looks for.
+fn to_animated_svg(wires: &[Wire], duration: f64) -> String {
+    let paths: Vec<Vec<Coord>> = wires.iter().map(vertices).collect();
+    let longest = wires
+        .iter()
+        .filter_map(|wire| wire.last())
+        .map(|last| last.steps + last.length())
+        .max()
+        .unwrap_or(0);
+    let tick = duration / longest.max(1) as f64;
+    // Times are written to a hundredth of a second, or finer when a tick is shorter than that, so
+    // every step starts at its own time, down to as fine as an f64 can tell apart
+    let digits = ((-tick.log10()).ceil() + 1.0).clamp(2.0, 17.0) as usize;
+    let (mut svg, unit) = open(&paths);
+
+    for (i, wire) in wires.iter().enumerate() {
+        for segment in wire.iter() {
+            let ((x1, y1, _), (x2, y2, _)) = (segment.start, segment.end);
+            let begin = segment.steps as f64 * tick;
+            let dur = segment.length() as f64 * tick;
+            writeln!(
+                svg,
+                r#"<line class="wire" data-wire="{}" x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="{:.2}">"#,
+                i + 1,
+                x1,
+                flip(y1),
+                x1,
+                flip(y1),
+                COLOURS[i % COLOURS.len()],
+                unit
+            )
+            .unwrap();
+            for (attribute, from, to) in
+                [("x2", x1 as i128, x2 as i128), ("y2", flip(y1), flip(y2))].iter()
+            {
+                writeln!(
+                    svg,
+                    r#"<animate attributeName="{}" from="{}" to="{}" begin="{:.*}s" dur="{:.*}s" fill="freeze"/>"#,
+                    attribute, from, to, digits, begin, digits, dur
+                )
+                .unwrap();
+            }
+            svg.push_str("</line>\n");
+        }
+    }
+
+    for run in shared_runs(wires).iter() {
+        let (x, y, z) = run.from;
+        let found = last_reached(run) as f64 * tick;
+        let steps = run.total_steps(run.from);
+        let (x2, y2, _) = run.to();
+        writeln!(
+            svg,
+            r#"<line class="intersection" x1="{}" y1="{}" x2="{}" y2="{}" stroke="gold" stroke-width="{:.2}" stroke-linecap="round" opacity="0">"#,
+            x,
+            flip(y),
+            x2,
+            flip(y2),
+            unit * 6.0
+        )
+        .unwrap();
+        writeln!(
+            svg,
+            r#"<animate attributeName="opacity" values="1;0;1;0;1" begin="{:.*}s" dur="{:.2}s" fill="freeze"/>"#,
+            digits,
+            found,
+            (duration / 10.0).clamp(0.5, 2.0)
+        )
+        .unwrap();
+        svg.push_str("</line>\n");
+        writeln!(
+            svg,
+            r#"<text x="{:.2}" y="{:.2}" font-size="{:.2}" opacity="0">{:?}: s={}<set attributeName="opacity" to="1" begin="{:.*}s" fill="freeze"/></text>"#,
+            x as f64 + unit * 4.0,
+            -(y as f64) - unit * 4.0,
+            unit * 6.0,
+            (x, y, z),
+            steps,
+            digits,
+            found
+        )
+        .unwrap();
+    }
+
+    writeln!(
+        svg,
+        r#"<circle class="origin" cx="0" cy="0" r="{:.2}" fill="none" stroke="black" stroke-width="{:.2}"/>"#,
+        unit * 4.0,
+        unit
+    )
+    .unwrap();
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day3::get_wire;
+
+    #[test]
+    fn test_to_svg() {
+        let wires = vec![
+            get_wire("R8,U5,L5,D3").unwrap(),
+            get_wire("U7,R6,D4,L4").unwrap(),
+        ];
+        let svg = to_svg(&wires);
+
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        assert!(svg.contains(r#"data-wire="1" points="0,0 8,0 8,-5 3,-5 3,-2""#));
+        assert!(svg.contains(r#"data-wire="2" points="0,0 0,-7 6,-7 6,-3 2,-3""#));
+        assert!(svg.contains(r#"class="origin" cx="0" cy="0""#));
+        // (3, 3, 0) is closest by manhattan distance, (6, 5, 0) by steps
+        assert!(svg.contains(r#"class="closest" cx="3" cy="-3""#));
+        assert!(svg.contains(r#"class="closest" cx="6" cy="-5""#));
+        assert!(svg.contains(">d=6 s=40<"));
+        assert!(svg.contains(">d=11 s=30<"));
+        assert_eq!(2, svg.matches("<polyline").count());
+        assert_eq!(3, svg.matches("<circle").count());
+    }
+
+    // Steps and extents past what an i64 can hold are still drawn
+    #[test]
+    fn test_huge_svg() {
+        let wires = vec![
+            get_wire("U1,R9000000000000000000,D2").unwrap(),
+            get_wire("D1,R9000000000000000000,U2").unwrap(),
+            get_wire("L9000000000000000000").unwrap(),
+        ];
+        let svg = to_svg(&wires);
+        assert!(svg.contains(">d=9000000000000000001 s=18000000000000000004<"));
+        assert!(svg.contains(r#"points="0,0 -9000000000000000000,0""#));
+        let svg = to_animated_svg(&wires, 10.0);
+        assert!(svg.contains(">(9000000000000000000, 1, 0): s=18000000000000000004<"));
+    }
+
+    #[test]
+    fn test_overlap_svg() {
+        let wires = vec![
+            get_wire("R1000000000").unwrap(),
+            get_wire("R1000000000").unwrap(),
+        ];
+        let svg = to_svg(&wires);
+        assert!(svg.contains(r#"<line class="overlap" x1="1" y1="0" x2="999999999" y2="0""#));
+        assert_eq!(1, svg.matches(r#"class="closest""#).count());
+
+        // The overlap is only all found once both wires have reached its far end. Each step takes
+        // a microsecond, so times are written finely enough to tell the steps apart.
+        let svg = to_animated_svg(&wires, 1000.0);
+        assert!(svg.contains(r#"values="1;0;1;0;1" begin="1000.0000000s""#));
+        let wires = vec![
+            get_wire("R1000000000").unwrap(),
+            get_wire("U1,D1,R999999998").unwrap(),
+        ];
+        let svg = to_animated_svg(&wires, 1000.0);
+        assert!(svg.contains(r#"begin="0.0000020s" dur="999.9999980s""#));
+    }
+
+    #[test]
+    fn test_to_animated_svg() {
+        let wires = vec![
+            get_wire("R8,U5,L5,D3").unwrap(),
+            get_wire("U7,R6,D4,L4").unwrap(),
+        ];
+        // Both wires are 21 steps long, so each step takes a second
+        let svg = to_animated_svg(&wires, 21.0);
+
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(8, svg.matches(r#"<line class="wire""#).count());
+        assert_eq!(8, svg.matches(r#"<animate attributeName="x2""#).count());
+        assert_eq!(8, svg.matches(r#"<animate attributeName="y2""#).count());
+        // The second wire's third segment, D4, starts after 13 steps
+        assert!(svg.contains(
+            r#"<animate attributeName="y2" from="-7" to="-3" begin="13.00s" dur="4.00s" fill="freeze"/>"#
+        ));
+        // Both wires reach (6, 5, 0) after 15 steps, and (3, 3, 0) after 20
+        assert!(svg.contains(r#"values="1;0;1;0;1" begin="15.00s""#));
+        assert!(svg.contains(r#"values="1;0;1;0;1" begin="20.00s""#));
+        assert!(svg.contains(">(6, 5, 0): s=30<"));
+        assert!(svg.contains(">(3, 3, 0): s=40<"));
+    }
+}
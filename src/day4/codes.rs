@@ -0,0 +1,153 @@
+use super::count::Pair;
+use super::rules::{self, digits, Code};
+use super::Range;
+use std::char;
+
+// Prints each code in the range that follows the puzzle's rules, as it's found, written in the
+// range's base
+pub fn list(range: &Range, length: usize, pair: Pair) {
+    for code in valid(range, length, pair) {
+        let text: String = digits(code, range.radix)
+            .into_iter()
+            .map(|digit| char::from_digit(digit, range.radix).unwrap())
+            .collect();
+        println!("{}", text);
+    }
+}
+
+// The codes in the range `length` digits long with digits that never decrease and a pair, in
+// order. Only codes of that length with digits that never decrease are looked at, and each pair
+// rule is checked on those.
+pub fn valid(range: &Range, length: usize, pair: Pair) -> impl Iterator<Item = u64> {
+    let rule = rules::puzzle(length, pair);
+    let radix = range.radix;
+    // Only numbers written with exactly `length` digits can be codes
+    let power = |n: usize| (radix as u64).checked_pow(n as u32);
+    let (lowest, highest) = match (length, power(length.saturating_sub(1))) {
+        (0, _) | (_, None) => (1, 0),
+        (1, _) => (0, radix as u64 - 1),
+        (_, Some(lowest)) => (lowest, power(length).map_or(u64::MAX, |limit| limit - 1)),
+    };
+    let clamped = Range {
+        start: range.start.max(lowest),
+        end: range.end.min(highest),
+        radix,
+    };
+    NonDecreasing::new(&clamped).filter(move |&value| rule.check(&Code::new(value, radix)))
+}
+
+// The numbers in a range with digits that never decrease, in order. After each one it jumps
+// straight to the next, rather than stepping through all the numbers between.
+pub struct NonDecreasing {
+    next: Option<u64>,
+    end: u64,
+    radix: u32,
+}
+
+impl NonDecreasing {
+    pub fn new(range: &Range) -> NonDecreasing {
+        NonDecreasing {
+            next: next_non_decreasing(range.start, range.radix),
+            end: range.end,
+            radix: range.radix,
+        }
+    }
+}
+
+impl Iterator for NonDecreasing {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        let value = self.next.filter(|&value| value <= self.end)?;
+        self.next = value
+            .checked_add(1)
+            .and_then(|next| next_non_decreasing(next, self.radix));
+        Some(value)
+    }
+}
+
+// The smallest number from `value` on with digits that never decrease. Where a digit first drops
+// below the one before, it and every digit after are raised to match, so 130254 becomes 133333.
+// There isn't one if it's too big to fit.
+fn next_non_decreasing(value: u64, radix: u32) -> Option<u64> {
+    let mut digits = digits(value, radix);
+    if let Some(drop) = (1..digits.len()).find(|&i| digits[i] < digits[i - 1]) {
+        let fill = digits[drop - 1];
+        for digit in digits[drop..].iter_mut() {
+            *digit = fill;
+        }
+    }
+    digits.iter().try_fold(0u64, |total, &digit| {
+        total.checked_mul(radix as u64)?.checked_add(digit as u64)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day4::count::count;
+
+    fn range(start: u64, end: u64, radix: u32) -> Range {
+        Range { start, end, radix }
+    }
+
+    #[test]
+    fn test_non_decreasing() {
+        assert_eq!(Some(133333), next_non_decreasing(130254, 10));
+        assert_eq!(Some(677777), next_non_decreasing(677000, 10));
+        assert_eq!(Some(222222), next_non_decreasing(199999 + 1, 10));
+        assert_eq!(Some(0), next_non_decreasing(0, 10));
+        assert_eq!(None, next_non_decreasing(u64::MAX, 10));
+        assert_eq!(Some(0b111), next_non_decreasing(0b101, 2));
+        assert_eq!(Some(0xaaa), next_non_decreasing(0xa09, 16));
+
+        let found: Vec<u64> = NonDecreasing::new(&range(95, 125, 10)).collect();
+        assert_eq!(
+            vec![99, 111, 112, 113, 114, 115, 116, 117, 118, 119, 122, 123, 124, 125],
+            found
+        );
+        for &radix in [2, 10, 16, 36].iter() {
+            let brute: Vec<u64> = (0..=5000)
+                .filter(|&value| {
+                    digits(value, radix)
+                        .windows(2)
+                        .all(|pair| pair[0] <= pair[1])
+                })
+                .collect();
+            let found: Vec<u64> = NonDecreasing::new(&range(0, 5000, radix)).collect();
+            assert_eq!(brute, found);
+        }
+        assert_eq!(0, NonDecreasing::new(&range(5, 4, 10)).count());
+        assert_eq!(
+            0,
+            NonDecreasing::new(&range(u64::MAX - 10, u64::MAX, 10)).count()
+        );
+    }
+
+    #[test]
+    fn test_valid() {
+        let puzzle = range(130254, 678275, 10);
+        assert_eq!(2090, valid(&puzzle, 6, Pair::Adjacent).count());
+        assert_eq!(1419, valid(&puzzle, 6, Pair::Exact).count());
+        let first: Vec<u64> = valid(&puzzle, 6, Pair::Exact).take(3).collect();
+        assert_eq!(vec![133344, 133355, 133366], first);
+
+        // Long codes stream without stepping through every number
+        let long = range(10u64.pow(17), 10u64.pow(18) - 1, 10);
+        let first: Vec<u64> = valid(&long, 18, Pair::Exact).take(2).collect();
+        assert_eq!(vec![111111111111111122, 111111111111111133], first);
+        assert_eq!(0, valid(&range(0, u64::MAX, 10), 0, Pair::Adjacent).count());
+        assert_eq!(
+            0,
+            valid(&range(0, u64::MAX, 10), 21, Pair::Adjacent).count()
+        );
+        assert_eq!(1, valid(&range(0, u64::MAX, 2), 64, Pair::Adjacent).count());
+        for &(radix, length) in [(10, 9), (10, 12), (16, 7), (16, 9), (3, 24)].iter() {
+            let wide = range(123_456_789, 234_567_890_123, radix);
+            assert_eq!(
+                count(&wide, length, Pair::Exact),
+                valid(&wide, length, Pair::Exact).count() as u64
+            );
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use super::rules::digits;
+use super::Range;
+use std::collections::HashMap;
+
+// Which pairs of digits count towards the puzzle's rules
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Pair {
+    // Part 1: any two digits next to each other that are the same
+    Adjacent,
+    // Part 2: a run of exactly two of the same digit
+    Exact,
+}
+
+impl Pair {
+    fn counts(self, run: usize) -> bool {
+        match self {
+            Pair::Adjacent => run >= 2,
+            Pair::Exact => run == 2,
+        }
+    }
+}
+
+pub fn report(range: &Range, length: usize, pair: Pair) {
+    println!(
+        "{} values are possible passcodes",
+        count(range, length, pair)
+    );
+}
+
+// Counts the codes `length` digits long in `start..=end` with digits that never decrease and a
+// pair, without looking at each code. The codes up to `end` are counted a digit at a time, and
+// those before `start` taken away.
+pub fn count(range: &Range, length: usize, pair: Pair) -> u64 {
+    if range.start > range.end {
+        return 0;
+    }
+    let below = match range.start.checked_sub(1) {
+        Some(before) => count_up_to(before, length, range.radix, pair),
+        None => 0,
+    };
+    count_up_to(range.end, length, range.radix, pair) - below
+}
+
+// Longer runs than this count the same for either kind of pair, so they're all kept as this
+const LONG_RUN: usize = 3;
+
+// The state after placing some digits: how many, the last one, how long its run is and whether a
+// pair has already been made
+type State = (usize, u32, usize, bool);
+
+fn count_up_to(bound: u64, length: usize, radix: u32, pair: Pair) -> u64 {
+    let digits = match digits(bound, radix) {
+        digits if digits.len() < length => return 0,
+        // Every code of the length is below the bound
+        digits if digits.len() > length => vec![radix - 1; length],
+        digits => digits,
+    };
+
+    let mut search = Search {
+        digits: &digits,
+        radix,
+        pair,
+        memo: HashMap::new(),
+    };
+    search.count((0, 0, 0, false), true)
+}
+
+struct Search<'a> {
+    digits: &'a [u32],
+    radix: u32,
+    pair: Pair,
+    // Counts from states where the digits placed so far are already below the bound's, so any
+    // digits can follow
+    memo: HashMap<State, u64>,
+}
+
+impl Search<'_> {
+    // Counts the ways to finish a code from a state. While `tight`, the digits placed match the
+    // bound's so far and the next can't go above the bound's.
+    fn count(&mut self, state: State, tight: bool) -> u64 {
+        let (placed, last, run, paired) = state;
+        if placed == self.digits.len() {
+            return (paired || self.pair.counts(run)) as u64;
+        }
+        if !tight {
+            if let Some(&found) = self.memo.get(&state) {
+                return found;
+            }
+        }
+
+        // Codes don't start with 0, and digits never decrease
+        let lowest = last.max(1);
+        let highest = if tight {
+            self.digits[placed]
+        } else {
+            self.radix - 1
+        };
+        let mut total = 0;
+        for digit in lowest..=highest {
+            let next = if placed > 0 && digit == last {
+                (placed + 1, digit, (run + 1).min(LONG_RUN), paired)
+            } else {
+                (placed + 1, digit, 1, paired || self.pair.counts(run))
+            };
+            total += self.count(next, tight && digit == highest);
+        }
+
+        if !tight {
+            self.memo.insert(state, total);
+        }
+        total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day4::{count as brute_force, rules};
+    use crate::rng::Rng;
+
+    fn range(start: u64, end: u64, radix: u32) -> Range {
+        Range { start, end, radix }
+    }
+
+    #[test]
+    fn test_matches_puzzle() {
+        let puzzle = range(130254, 678275, 10);
+        for (pair, rule) in [
+            (Pair::Adjacent, rules::part1()),
+            (Pair::Exact, rules::part2()),
+        ] {
+            assert_eq!(
+                brute_force(&puzzle, rule.as_ref()) as u64,
+                count(&puzzle, 6, pair)
+            );
+        }
+        assert_eq!(2090, count(&puzzle, 6, Pair::Adjacent));
+        assert_eq!(1419, count(&puzzle, 6, Pair::Exact));
+    }
+
+    #[test]
+    fn test_random_ranges() {
+        let mut rng = Rng::new(48);
+        for _ in 0..200 {
+            let radix = rng.range(2, 36) as u32;
+            let length = rng.range(1, 5) as usize;
+            let start = rng.range(0, 120_000) as u64;
+            let range = range(start, start + rng.range(0, 20_000) as u64, radix);
+            for &pair in [Pair::Adjacent, Pair::Exact].iter() {
+                assert_eq!(
+                    brute_force(&range, rules::puzzle(length, pair).as_ref()) as u64,
+                    count(&range, length, pair),
+                    "{}..={} in base {} with {} digits",
+                    range.start,
+                    range.end,
+                    radix,
+                    length
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_long_codes() {
+        // Every non-decreasing code of 18 digits from 1 to 9 has a pair, and there are 26 choose 8
+        let long = range(10u64.pow(17), 10u64.pow(18) - 1, 10);
+        assert_eq!(1_562_275, count(&long, 18, Pair::Adjacent));
+        // Most long codes only have long runs
+        let exact = count(&long, 18, Pair::Exact);
+        assert!(exact > 0 && exact < 1_562_275);
+        assert_eq!(0, count(&range(0, u64::MAX, 10), 21, Pair::Adjacent));
+        assert_eq!(0, count(&range(5, 4, 10), 1, Pair::Adjacent));
+        // Single digits have nothing to pair with
+        assert_eq!(0, count(&range(0, 9, 10), 1, Pair::Adjacent));
+        assert_eq!(9, count(&range(0, 99, 10), 2, Pair::Exact));
+        // The only binary codes with digits that never decrease are all ones
+        assert_eq!(1, count(&range(0, u64::MAX, 2), 64, Pair::Adjacent));
+        assert_eq!(0, count(&range(0, u64::MAX, 2), 64, Pair::Exact));
+        assert_eq!(1, count(&range(0, 3, 2), 2, Pair::Exact));
+    }
+}
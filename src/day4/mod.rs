@@ -0,0 +1,123 @@
+pub mod codes;
+pub mod count;
+pub mod rules;
+
+use count::Pair;
+use rules::{Code, Rule};
+
+// The codes to look through: those from `start` to `end`, written in base `radix`
+pub struct Range {
+    pub start: u64,
+    pub end: u64,
+    pub radix: u32,
+}
+
+// Reads the ends of a range written in the given base, printing what's wrong if it can't
+pub fn parse_range(start: &str, end: &str, radix: &str) -> Option<Range> {
+    let radix = match radix.parse() {
+        Ok(radix) if (2..=36).contains(&radix) => radix,
+        _ => {
+            println!("The radix must be a number from 2 to 36");
+            return None;
+        }
+    };
+    let read = |value: &str| match u64::from_str_radix(value, radix) {
+        Ok(value) => Some(value),
+        Err(_) => {
+            println!("Could not read '{}' as a base {} number", value, radix);
+            None
+        }
+    };
+    Some(Range {
+        start: read(start)?,
+        end: read(end)?,
+        radix,
+    })
+}
+
+pub fn part1(range: &Range, length: usize) {
+    println!(
+        "{} values are possible passcodes",
+        count(range, rules::puzzle(length, Pair::Adjacent).as_ref())
+    );
+}
+
+#[cfg(test)]
+fn is_valid_part1_passcode(test_code: u64) -> bool {
+    rules::part1().check(&Code::new(test_code, 10))
+}
+
+pub fn part2(range: &Range, length: usize) {
+    println!(
+        "{} values are possible passcodes",
+        count(range, rules::puzzle(length, Pair::Exact).as_ref())
+    );
+}
+
+#[cfg(test)]
+fn is_valid_part2_passcode(test_code: u64) -> bool {
+    rules::part2().check(&Code::new(test_code, 10))
+}
+
+// Counts the codes in the range that follow the rules given as an expression, like
+// `length=6 & non-decreasing & (exact-pair | !forbidden=9)`.
+pub fn count_report(range: &Range, spec: &str) {
+    match rules::parse(spec) {
+        Ok(rule) => println!(
+            "{} values are possible passcodes",
+            count(range, rule.as_ref())
+        ),
+        Err(why) => println!("Could not understand the rules: {}", why),
+    }
+}
+
+fn count(range: &Range, rule: &dyn Rule) -> usize {
+    (range.start..=range.end)
+        .filter(|&value| rule.check(&Code::new(value, range.radix)))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_range() {
+        let range = parse_range("130254", "678275", "10").unwrap();
+        assert_eq!((130254, 678275, 10), (range.start, range.end, range.radix));
+        let range = parse_range("101", "zz", "36").unwrap();
+        assert_eq!((36 * 36 + 1, 36 * 36 - 1), (range.start, range.end));
+        assert!(parse_range("0", "1", "1").is_none());
+        assert!(parse_range("0", "1", "37").is_none());
+        assert!(parse_range("0", "2", "2").is_none());
+        assert!(parse_range("-1", "1", "10").is_none());
+    }
+
+    #[test]
+    fn test_is_valid_part1_passcode() {
+        assert!(!is_valid_part1_passcode(12345)); // Too short
+        assert!(!is_valid_part1_passcode(223450)); // Descending pair
+        assert!(!is_valid_part1_passcode(123789)); // No double
+        assert!(!is_valid_part1_passcode(135679)); // No double
+        assert!(is_valid_part1_passcode(122345));
+        assert!(is_valid_part1_passcode(111123));
+        assert!(is_valid_part1_passcode(335679));
+        assert!(is_valid_part1_passcode(111111));
+    }
+
+    #[test]
+    fn test_is_valid_part2_passcode() {
+        assert!(!is_valid_part2_passcode(12345)); // Too short
+        assert!(!is_valid_part2_passcode(223450)); // Descending pair
+        assert!(!is_valid_part2_passcode(123789)); // No double
+        assert!(!is_valid_part2_passcode(135679)); // No double
+        assert!(!is_valid_part2_passcode(111123)); // Only double is in long run
+        assert!(!is_valid_part2_passcode(123444)); // Only double is in long run
+        assert!(!is_valid_part2_passcode(111111)); // Only double is in long run
+        assert!(is_valid_part2_passcode(112233));
+        assert!(is_valid_part2_passcode(111122));
+        assert!(is_valid_part2_passcode(112222));
+        assert!(is_valid_part2_passcode(122345));
+        assert!(is_valid_part2_passcode(335679));
+    }
+}
@@ -0,0 +1,403 @@
+use super::count::Pair;
+use std::fmt;
+
+// A passcode with its digits split out, most significant first, so each rule doesn't have to
+pub struct Code {
+    value: u64,
+    digits: Vec<u32>,
+}
+
+impl Code {
+    pub fn new(value: u64, radix: u32) -> Code {
+        Code {
+            value,
+            digits: digits(value, radix),
+        }
+    }
+
+    // How long each run of the same digit is, in order
+    fn runs(&self) -> Vec<usize> {
+        let mut runs: Vec<usize> = vec![];
+        for (i, digit) in self.digits.iter().enumerate() {
+            match i.checked_sub(1).map(|prev| self.digits[prev]) {
+                Some(prev) if prev == *digit => *runs.last_mut().unwrap() += 1,
+                _ => runs.push(1),
+            }
+        }
+        runs
+    }
+}
+
+// The digits of a value written in base `radix`, most significant first
+pub fn digits(mut value: u64, radix: u32) -> Vec<u32> {
+    let mut digits = vec![(value % radix as u64) as u32];
+    value /= radix as u64;
+    while value > 0 {
+        digits.push((value % radix as u64) as u32);
+        value /= radix as u64;
+    }
+    digits.reverse();
+    digits
+}
+
+pub trait Rule {
+    fn check(&self, code: &Code) -> bool;
+}
+
+struct Length(usize);
+struct InRange(u64, u64);
+struct NonDecreasing;
+// Two digits next to each other are the same
+struct AdjacentPair;
+// Two digits next to each other are the same, and not part of a longer run
+struct ExactPair;
+struct ForbiddenDigits(Vec<u32>);
+struct DigitSum(u32, u32);
+struct And(Vec<Box<dyn Rule>>);
+struct Or(Vec<Box<dyn Rule>>);
+struct Not(Box<dyn Rule>);
+
+impl Rule for Length {
+    fn check(&self, code: &Code) -> bool {
+        code.digits.len() == self.0
+    }
+}
+
+impl Rule for InRange {
+    fn check(&self, code: &Code) -> bool {
+        (self.0..=self.1).contains(&code.value)
+    }
+}
+
+impl Rule for NonDecreasing {
+    fn check(&self, code: &Code) -> bool {
+        code.digits.windows(2).all(|pair| pair[0] <= pair[1])
+    }
+}
+
+impl Rule for AdjacentPair {
+    fn check(&self, code: &Code) -> bool {
+        code.runs().iter().any(|&run| run >= 2)
+    }
+}
+
+impl Rule for ExactPair {
+    fn check(&self, code: &Code) -> bool {
+        code.runs().contains(&2)
+    }
+}
+
+impl Rule for ForbiddenDigits {
+    fn check(&self, code: &Code) -> bool {
+        !code.digits.iter().any(|digit| self.0.contains(digit))
+    }
+}
+
+impl Rule for DigitSum {
+    fn check(&self, code: &Code) -> bool {
+        (self.0..=self.1).contains(&code.digits.iter().sum())
+    }
+}
+
+impl Rule for And {
+    fn check(&self, code: &Code) -> bool {
+        self.0.iter().all(|rule| rule.check(code))
+    }
+}
+
+impl Rule for Or {
+    fn check(&self, code: &Code) -> bool {
+        self.0.iter().any(|rule| rule.check(code))
+    }
+}
+
+impl Rule for Not {
+    fn check(&self, code: &Code) -> bool {
+        !self.0.check(code)
+    }
+}
+
+// The rules from each part of the puzzle
+pub fn part1() -> Box<dyn Rule> {
+    puzzle(6, Pair::Adjacent)
+}
+
+pub fn part2() -> Box<dyn Rule> {
+    puzzle(6, Pair::Exact)
+}
+
+// Codes `length` digits long with digits that never decrease and the given kind of pair
+pub fn puzzle(length: usize, pair: Pair) -> Box<dyn Rule> {
+    let pair: Box<dyn Rule> = match pair {
+        Pair::Adjacent => Box::new(AdjacentPair),
+        Pair::Exact => Box::new(ExactPair),
+    };
+    Box::new(And(vec![
+        Box::new(Length(length)),
+        Box::new(NonDecreasing),
+        pair,
+    ]))
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    UnknownRule(String),
+    BadArgument(String),
+    Unexpected(String),
+    UnexpectedEnd,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnknownRule(name) => write!(f, "There is no rule called '{}'", name),
+            Error::BadArgument(rule) => write!(f, "Could not understand '{}'", rule),
+            Error::Unexpected(token) => write!(f, "Did not expect '{}'", token),
+            Error::UnexpectedEnd => write!(f, "The rules end too soon"),
+        }
+    }
+}
+
+// Reads rules combined with `&`, `|` and `!`, which bind from loosest to tightest in that order,
+// and brackets. The rules are `length=N`, `range=LOW-HIGH`, `non-decreasing`, `adjacent-pair`,
+// `exact-pair`, `forbidden=DIGITS` with letters for digits past 9, `digit-sum=N` or
+// `digit-sum=LOW-HIGH`, and `part1` and `part2` for the puzzle's own.
+pub fn parse(spec: &str) -> Result<Box<dyn Rule>, Error> {
+    let tokens = tokenise(spec);
+    let mut parser = Parser { tokens, at: 0 };
+    let rule = parser.any()?;
+    match parser.tokens.get(parser.at) {
+        Some(token) => Err(Error::Unexpected(token.clone())),
+        None => Ok(rule),
+    }
+}
+
+fn tokenise(spec: &str) -> Vec<String> {
+    let mut tokens = vec![];
+    let mut name = String::new();
+    for c in spec.chars() {
+        if c.is_whitespace() || "&|!()".contains(c) {
+            if !name.is_empty() {
+                tokens.push(std::mem::take(&mut name));
+            }
+            if !c.is_whitespace() {
+                tokens.push(c.to_string());
+            }
+        } else {
+            name.push(c);
+        }
+    }
+    if !name.is_empty() {
+        tokens.push(name);
+    }
+    tokens
+}
+
+struct Parser {
+    tokens: Vec<String>,
+    at: usize,
+}
+
+impl Parser {
+    fn next_if(&mut self, token: &str) -> bool {
+        let found = self.tokens.get(self.at).is_some_and(|t| t == token);
+        if found {
+            self.at += 1;
+        }
+        found
+    }
+
+    // Rules joined by `|`
+    fn any(&mut self) -> Result<Box<dyn Rule>, Error> {
+        let mut rules = vec![self.all()?];
+        while self.next_if("|") {
+            rules.push(self.all()?);
+        }
+        Ok(if rules.len() == 1 {
+            rules.pop().unwrap()
+        } else {
+            Box::new(Or(rules))
+        })
+    }
+
+    // Rules joined by `&`
+    fn all(&mut self) -> Result<Box<dyn Rule>, Error> {
+        let mut rules = vec![self.single()?];
+        while self.next_if("&") {
+            rules.push(self.single()?);
+        }
+        Ok(if rules.len() == 1 {
+            rules.pop().unwrap()
+        } else {
+            Box::new(And(rules))
+        })
+    }
+
+    fn single(&mut self) -> Result<Box<dyn Rule>, Error> {
+        if self.next_if("!") {
+            return Ok(Box::new(Not(self.single()?)));
+        }
+        if self.next_if("(") {
+            let rule = self.any()?;
+            if !self.next_if(")") {
+                return match self.tokens.get(self.at) {
+                    Some(token) => Err(Error::Unexpected(token.clone())),
+                    None => Err(Error::UnexpectedEnd),
+                };
+            }
+            return Ok(rule);
+        }
+
+        let token = match self.tokens.get(self.at) {
+            Some(token) if !"&|)".contains(token.as_str()) => token.clone(),
+            Some(token) => return Err(Error::Unexpected(token.clone())),
+            None => return Err(Error::UnexpectedEnd),
+        };
+        self.at += 1;
+        named(&token)
+    }
+}
+
+const NAMES: [&str; 9] = [
+    "part1",
+    "part2",
+    "non-decreasing",
+    "adjacent-pair",
+    "exact-pair",
+    "length",
+    "range",
+    "digit-sum",
+    "forbidden",
+];
+
+fn named(token: &str) -> Result<Box<dyn Rule>, Error> {
+    let (name, argument) = match token.split_once('=') {
+        Some((name, argument)) => (name, Some(argument)),
+        None => (token, None),
+    };
+    let bad = || Error::BadArgument(token.to_string());
+    let rule: Box<dyn Rule> = match (name, argument) {
+        ("part1", None) => part1(),
+        ("part2", None) => part2(),
+        ("non-decreasing", None) => Box::new(NonDecreasing),
+        ("adjacent-pair", None) => Box::new(AdjacentPair),
+        ("exact-pair", None) => Box::new(ExactPair),
+        ("length", Some(n)) => Box::new(Length(n.parse().map_err(|_| bad())?)),
+        ("range", Some(range)) => {
+            let (low, high) = bounds(range).ok_or_else(bad)?;
+            Box::new(InRange(low, high))
+        }
+        ("digit-sum", Some(range)) => {
+            let (low, high) = bounds(range).ok_or_else(bad)?;
+            Box::new(DigitSum(low, high))
+        }
+        ("forbidden", Some(digits)) => {
+            let digits: Option<Vec<u32>> = digits.chars().map(|c| c.to_digit(36)).collect();
+            Box::new(ForbiddenDigits(digits.ok_or_else(bad)?))
+        }
+        _ if NAMES.contains(&name) => return Err(bad()),
+        _ => return Err(Error::UnknownRule(name.to_string())),
+    };
+    Ok(rule)
+}
+
+// Reads `N` as `N-N`, or `LOW-HIGH`
+fn bounds<T: std::str::FromStr + Copy>(spec: &str) -> Option<(T, T)> {
+    match spec.split_once('-') {
+        Some((low, high)) => Some((low.parse().ok()?, high.parse().ok()?)),
+        None => {
+            let n = spec.parse().ok()?;
+            Some((n, n))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(spec: &str, value: u64) -> bool {
+        parse(spec).unwrap().check(&Code::new(value, 10))
+    }
+
+    #[test]
+    fn test_built_ins() {
+        let code = Code::new(112333, 10);
+        assert_eq!(vec![2, 1, 3], code.runs());
+        assert!(Length(6).check(&code));
+        assert!(!Length(5).check(&code));
+        assert!(InRange(112333, 112333).check(&code));
+        assert!(!InRange(0, 112332).check(&code));
+        assert!(NonDecreasing.check(&code));
+        assert!(!NonDecreasing.check(&Code::new(1121, 10)));
+        assert!(AdjacentPair.check(&code));
+        assert!(ExactPair.check(&code));
+        assert!(!ExactPair.check(&Code::new(111234, 10)));
+        assert!(ForbiddenDigits(vec![0, 9]).check(&code));
+        assert!(!ForbiddenDigits(vec![2]).check(&code));
+        assert!(DigitSum(13, 13).check(&code));
+        assert!(!DigitSum(14, 20).check(&code));
+
+        // Other bases
+        assert_eq!(vec![1, 0, 1, 1], digits(11, 2));
+        assert_eq!(vec![0], digits(0, 7));
+        assert_eq!(vec![35, 0], digits(35 * 36, 36));
+        let code = Code::new(0b1011, 2);
+        assert!(Length(4).check(&code));
+        assert!(!NonDecreasing.check(&code));
+        assert!(ExactPair.check(&code));
+        assert!(!ForbiddenDigits(vec![35]).check(&Code::new(35, 36)));
+        assert!(parse("forbidden=z").unwrap().check(&Code::new(34, 36)));
+    }
+
+    #[test]
+    fn test_combinators() {
+        let yes = || -> Box<dyn Rule> { Box::new(Length(3)) };
+        let no = || -> Box<dyn Rule> { Box::new(Length(4)) };
+        let code = Code::new(123, 10);
+        assert!(And(vec![yes(), yes()]).check(&code));
+        assert!(!And(vec![yes(), no()]).check(&code));
+        assert!(And(vec![]).check(&code));
+        assert!(Or(vec![no(), yes()]).check(&code));
+        assert!(!Or(vec![no(), no()]).check(&code));
+        assert!(Not(no()).check(&code));
+    }
+
+    #[test]
+    fn test_parse() {
+        assert!(check("part1", 111111));
+        assert!(!check("part2", 111111));
+        assert!(check("length=6 & non-decreasing", 123456));
+        // `&` binds tighter than `|`, and `!` tighter than both
+        assert!(check("length=2 & exact-pair | digit-sum=6", 123));
+        assert!(!check("length=2 & (exact-pair | digit-sum=6)", 123));
+        assert!(check("!forbidden=4 | length=1", 345));
+        assert!(!check("!(forbidden=4 | length=3)", 345));
+        assert!(check("!!non-decreasing", 345));
+        assert!(check("range=100-200 & digit-sum=2-5", 112));
+        assert!(!check("range=100-200 & digit-sum=2-5", 199));
+
+        assert_eq!(
+            Some(Error::UnknownRule(String::from("pair"))),
+            parse("pair").err()
+        );
+        assert_eq!(
+            Some(Error::BadArgument(String::from("length=x"))),
+            parse("length=x").err()
+        );
+        assert_eq!(
+            Some(Error::BadArgument(String::from("exact-pair=2"))),
+            parse("exact-pair=2").err()
+        );
+        assert_eq!(
+            Some(Error::Unexpected(String::from(")"))),
+            parse("part1)").err()
+        );
+        assert_eq!(
+            Some(Error::Unexpected(String::from("&"))),
+            parse("part1 | & part2").err()
+        );
+        assert_eq!(Some(Error::UnexpectedEnd), parse("(part1 & part2").err());
+        assert_eq!(Some(Error::UnexpectedEnd), parse("").err());
+    }
+}
@@ -1,4 +1,3 @@
-use std::error::Error;
 use std::fs::File;
 use std::io::prelude::*;
 use std::path::Path;
@@ -8,21 +7,24 @@ pub fn as_string(file: &str) -> String {
     let display = path.display();
 
     let mut file = match File::open(&path) {
-        Err(why) => panic!("Couldn't open {}: {}", display, why.description()),
+        Err(why) => panic!("Couldn't open {}: {}", display, why),
         Ok(file) => file,
     };
 
     let mut s = String::new();
-    match file.read_to_string(&mut s) {
-        Err(why) => panic!("Couldn't read {}: {}", display, why.description()),
-        Ok(_) => (),
+    if let Err(why) = file.read_to_string(&mut s) {
+        panic!("Couldn't read {}: {}", display, why);
     }
 
     s
 }
 
 pub fn parse_comma_separated_ints(file: &str) -> Vec<i64> {
-    if let Ok(ints) = as_string(file).split(',').map(str::parse::<i64>).collect() {
+    if let Ok(ints) = as_string(file)
+        .split(',')
+        .map(|s| s.trim().parse::<i64>())
+        .collect()
+    {
         return ints;
     }
 
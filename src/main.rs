@@ -1,12 +1,37 @@
-use clap::App;
+use clap::{App, Arg};
 
 mod day1;
 mod day2;
 mod day3;
 mod day4;
 mod input;
+mod rng;
 
 fn main() {
+    // The size and base of day 4's codes, and the range to look through, written in that base
+    let code_args = [
+        Arg::with_name("length")
+            .long("length")
+            .takes_value(true)
+            .default_value("6")
+            .help("Number of digits in a code"),
+        Arg::with_name("radix")
+            .long("radix")
+            .takes_value(true)
+            .default_value("10")
+            .help("Base the codes are written in, from 2 to 36"),
+        Arg::with_name("start")
+            .long("start")
+            .takes_value(true)
+            .default_value("130254")
+            .help("First code in the range"),
+        Arg::with_name("end")
+            .long("end")
+            .takes_value(true)
+            .default_value("678275")
+            .help("Last code in the range"),
+    ];
+
     let matches = App::new("Advent of Code 2019")
         .version("1.0")
         .author("Peter Thompson <peter.thompson@dunelm.org.uk>")
@@ -17,20 +42,652 @@ fn main() {
         .subcommand(App::new("day2-2").about("Day 2: Part 2"))
         .subcommand(App::new("day3-1").about("Day 3: Part 1"))
         .subcommand(App::new("day3-2").about("Day 3: Part 2"))
-        .subcommand(App::new("day4-1").about("Day 4: Part 1"))
-        .subcommand(App::new("day4-2").about("Day 4: Part 2"))
+        .subcommand(
+            App::new("day3")
+                .about("Day 3: Wire tools")
+                .subcommand(
+                    App::new("crossings")
+                        .about("Report where any number of wires cross")
+                        .arg(
+                            Arg::with_name("FILE")
+                                .help("Input file with one wire per line")
+                                .default_value("day3"),
+                        )
+                        .arg(
+                            Arg::with_name("min-wires")
+                                .long("min-wires")
+                                .takes_value(true)
+                                .default_value("2")
+                                .help("Only count points crossed by at least this many wires"),
+                        ),
+                )
+                .subcommand(
+                    App::new("ascii")
+                        .about("Draw the wires in the terminal like the puzzle examples")
+                        .arg(
+                            Arg::with_name("FILE")
+                                .help("Input file with one wire per line")
+                                .default_value("day3"),
+                        )
+                        .arg(
+                            Arg::with_name("viewport")
+                                .long("viewport")
+                                .takes_value(true)
+                                .allow_hyphen_values(true)
+                                .help("Only draw the points from X1,Y1 to X2,Y2"),
+                        )
+                        .arg(
+                            Arg::with_name("scale")
+                                .long("scale")
+                                .takes_value(true)
+                                .default_value("1")
+                                .help("Draw each block of this many points square as one character"),
+                        )
+                        .arg(
+                            Arg::with_name("follow")
+                                .long("follow")
+                                .takes_value(true)
+                                .help("Only draw this wire, counting from 1"),
+                        ),
+                )
+                .subcommand(
+                    App::new("steps")
+                        .about("List the steps each wire takes to reach every intersection")
+                        .arg(
+                            Arg::with_name("FILE")
+                                .help("Input file with one wire per line")
+                                .default_value("day3"),
+                        )
+                        .arg(
+                            Arg::with_name("visit")
+                                .long("visit")
+                                .takes_value(true)
+                                .possible_values(&["first", "latest", "every"])
+                                .default_value("first")
+                                .help("Which visits to a point count when a wire passes it more than once"),
+                        ),
+                )
+                .subcommand(
+                    App::new("intersections")
+                        .about("List every intersection with how each wire reaches it")
+                        .arg(
+                            Arg::with_name("FILE")
+                                .help("Input file with one wire per line")
+                                .default_value("day3"),
+                        )
+                        .arg(
+                            Arg::with_name("sort")
+                                .long("sort")
+                                .takes_value(true)
+                                .possible_values(&["position", "distance", "steps"])
+                                .default_value("position")
+                                .help("Order to list the intersections in"),
+                        )
+                        .arg(
+                            Arg::with_name("format")
+                                .long("format")
+                                .takes_value(true)
+                                .possible_values(&["table", "csv", "json"])
+                                .default_value("table")
+                                .help("Format to write the list in"),
+                        )
+                        .arg(
+                            Arg::with_name("output")
+                                .long("output")
+                                .short("o")
+                                .takes_value(true)
+                                .help("File to write the list to, instead of printing it"),
+                        ),
+                )
+                .subcommand(
+                    App::new("closest")
+                        .about("Find the intersection closest to any point by any metric")
+                        .arg(
+                            Arg::with_name("FILE")
+                                .help("Input file with one wire per line")
+                                .default_value("day3"),
+                        )
+                        .arg(
+                            Arg::with_name("metric")
+                                .long("metric")
+                                .takes_value(true)
+                                .possible_values(&[
+                                    "manhattan",
+                                    "chebyshev",
+                                    "euclidean",
+                                    "squared-euclidean",
+                                ])
+                                .default_value("manhattan")
+                                .help("How to measure distance"),
+                        )
+                        .arg(
+                            Arg::with_name("origin")
+                                .long("origin")
+                                .takes_value(true)
+                                .allow_hyphen_values(true)
+                                .default_value("0,0")
+                                .help("Point to measure from, as X,Y or X,Y,Z"),
+                        ),
+                )
+                .subcommand(
+                    App::new("analyse")
+                        .about("Describe each wire on its own, with where it crosses itself")
+                        .arg(
+                            Arg::with_name("FILE")
+                                .help("Input file with one wire per line")
+                                .default_value("day3"),
+                        ),
+                )
+                .subcommand(
+                    App::new("reroute")
+                        .about("Suggest a path for the second wire that never crosses the first")
+                        .arg(
+                            Arg::with_name("FILE")
+                                .help("Input file with one wire per line")
+                                .default_value("day3"),
+                        )
+                        .arg(
+                            Arg::with_name("turn-penalty")
+                                .long("turn-penalty")
+                                .takes_value(true)
+                                .default_value("0")
+                                .help("Extra steps each turn costs"),
+                        ),
+                )
+                .subcommand(
+                    App::new("normalise")
+                        .about("Rewrite each wire with the fewest moves that trace the same path")
+                        .arg(
+                            Arg::with_name("FILE")
+                                .help("Input file with one wire per line")
+                                .default_value("day3"),
+                        )
+                        .arg(
+                            Arg::with_name("output")
+                                .long("output")
+                                .short("o")
+                                .takes_value(true)
+                                .help("File to write the wires to, instead of printing them"),
+                        ),
+                )
+                .subcommand(
+                    App::new("animate")
+                        .about("Animate the wires being laid down a step at a time as an SVG")
+                        .arg(
+                            Arg::with_name("FILE")
+                                .help("Input file with one wire per line")
+                                .default_value("day3"),
+                        )
+                        .arg(
+                            Arg::with_name("duration")
+                                .long("duration")
+                                .takes_value(true)
+                                .default_value("20")
+                                .help("Seconds the whole animation takes"),
+                        )
+                        .arg(
+                            Arg::with_name("output")
+                                .long("output")
+                                .short("o")
+                                .takes_value(true)
+                                .help("File to write the SVG to, instead of printing it"),
+                        ),
+                )
+                .subcommand(
+                    App::new("query")
+                        .about("Look up the wires through a point or the segments in an area")
+                        .arg(
+                            Arg::with_name("FILE")
+                                .help("Input file with one wire per line")
+                                .default_value("day3"),
+                        )
+                        .arg(
+                            Arg::with_name("point")
+                                .long("point")
+                                .takes_value(true)
+                                .allow_hyphen_values(true)
+                                .help("List the wires through X,Y or X,Y,Z and the steps to get there"),
+                        )
+                        .arg(
+                            Arg::with_name("area")
+                                .long("area")
+                                .takes_value(true)
+                                .allow_hyphen_values(true)
+                                .help("List the segments with a point from X1,Y1 to X2,Y2, or X1,Y1,Z1 to X2,Y2,Z2"),
+                        ),
+                )
+                .subcommand(
+                    App::new("render")
+                        .about("Draw the wires and their intersections as an SVG")
+                        .arg(
+                            Arg::with_name("FILE")
+                                .help("Input file with one wire per line")
+                                .default_value("day3"),
+                        )
+                        .arg(
+                            Arg::with_name("output")
+                                .long("output")
+                                .short("o")
+                                .takes_value(true)
+                                .help("File to write the SVG to, instead of printing it"),
+                        ),
+                ),
+        )
+        .subcommand(App::new("day4-1").about("Day 4: Part 1").args(&code_args))
+        .subcommand(App::new("day4-2").about("Day 4: Part 2").args(&code_args))
+        .subcommand(
+            App::new("intcode-test")
+                .about("Run the Intcode conformance cases")
+                .arg(
+                    Arg::with_name("DIR")
+                        .help("Directory of .case files")
+                        .default_value("./input/day2-cases"),
+                ),
+        )
+        .subcommand(
+            App::new("day4")
+                .about("Day 4: Passcode tools")
+                .subcommand(
+                    App::new("count")
+                        .about("Count the passcodes in a range that follow a set of rules")
+                        .arg(
+                            Arg::with_name("rules")
+                                .long("rules")
+                                .takes_value(true)
+                                .default_value("part2")
+                                .help("Rules to follow, like 'length=6 & non-decreasing & (exact-pair | !forbidden=9)'"),
+                        )
+                        .args(&code_args[1..]),
+                )
+                .subcommand(
+                    App::new("list")
+                        .about("List the passcodes in a range for a part of the puzzle, one per line")
+                        .arg(
+                            Arg::with_name("pair")
+                                .long("pair")
+                                .takes_value(true)
+                                .possible_values(&["adjacent", "exact"])
+                                .default_value("exact")
+                                .help("Pairs that count: any, as in part 1, or not part of a longer run, as in part 2"),
+                        )
+                        .args(&code_args),
+                )
+                .subcommand(
+                    App::new("tally")
+                        .about("Count the passcodes in a range for a part of the puzzle a digit at a time, for long codes and wide ranges")
+                        .arg(
+                            Arg::with_name("pair")
+                                .long("pair")
+                                .takes_value(true)
+                                .possible_values(&["adjacent", "exact"])
+                                .default_value("exact")
+                                .help("Pairs that count: any, as in part 1, or not part of a longer run, as in part 2"),
+                        )
+                        .args(&code_args),
+                ),
+        )
+        .subcommand(
+            App::new("gen")
+                .about("Generate random inputs")
+                .subcommand(
+                    App::new("wires")
+                        .about("Generate random wires for day 3, one per line")
+                        .arg(
+                            Arg::with_name("wires")
+                                .long("wires")
+                                .takes_value(true)
+                                .default_value("2")
+                                .help("Number of wires"),
+                        )
+                        .arg(
+                            Arg::with_name("segments")
+                                .long("segments")
+                                .takes_value(true)
+                                .default_value("301")
+                                .help("Number of segments in each wire"),
+                        )
+                        .arg(
+                            Arg::with_name("max-length")
+                                .long("max-length")
+                                .takes_value(true)
+                                .default_value("1000")
+                                .help("Longest a segment can be"),
+                        )
+                        .arg(
+                            Arg::with_name("intersections")
+                                .long("intersections")
+                                .takes_value(true)
+                                .help("Number of intersections to aim for"),
+                        )
+                        .arg(
+                            Arg::with_name("seed")
+                                .long("seed")
+                                .takes_value(true)
+                                .default_value("1")
+                                .help("Seed for the random wires"),
+                        )
+                        .arg(
+                            Arg::with_name("output")
+                                .long("output")
+                                .short("o")
+                                .takes_value(true)
+                                .help("File to write the wires to, instead of printing them"),
+                        ),
+                ),
+        )
+        .subcommand(
+            App::new("minimize")
+                .about("Shrink an Intcode program that fails to a minimal reproducer")
+                .arg(
+                    Arg::with_name("FILE")
+                        .help("Input file holding the failing program")
+                        .default_value("day2"),
+                )
+                .arg(
+                    Arg::with_name("noun")
+                        .long("noun")
+                        .takes_value(true)
+                        .requires("verb")
+                        .help("Noun to set before running"),
+                )
+                .arg(
+                    Arg::with_name("verb")
+                        .long("verb")
+                        .takes_value(true)
+                        .requires("noun")
+                        .help("Verb to set before running"),
+                ),
+        )
+        .subcommand(
+            App::new("optimize")
+                .about("Optimize an Intcode program and check it still behaves the same")
+                .arg(
+                    Arg::with_name("FILE")
+                        .help("Input file holding the program")
+                        .default_value("day2"),
+                )
+                .arg(
+                    Arg::with_name("trials")
+                        .long("trials")
+                        .takes_value(true)
+                        .default_value("1000")
+                        .help("Number of random noun/verb pairs to compare"),
+                )
+                .arg(
+                    Arg::with_name("seed")
+                        .long("seed")
+                        .takes_value(true)
+                        .default_value("1")
+                        .help("Seed for the random noun/verb pairs"),
+                ),
+        )
+        .subcommand(
+            App::new("intcode-run")
+                .about("Run an Intcode program with memory-mapped devices attached")
+                .arg(
+                    Arg::with_name("FILE")
+                        .help("Input file holding the program")
+                        .default_value("day2"),
+                )
+                .arg(
+                    Arg::with_name("device")
+                        .long("device")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help("Device to attach: console@ADDR, clock@ADDR, random@ADDR, framebuffer@ADDR:WxH, tiles@ADDR or ascii@ADDR"),
+                )
+                .arg(
+                    Arg::with_name("input")
+                        .long("input")
+                        .takes_value(true)
+                        .default_value("")
+                        .help("Text for consoles to read"),
+                )
+                .arg(
+                    Arg::with_name("seed")
+                        .long("seed")
+                        .takes_value(true)
+                        .default_value("1")
+                        .help("Seed for random devices"),
+                )
+                .arg(
+                    Arg::with_name("image")
+                        .long("image")
+                        .takes_value(true)
+                        .help("Save the tiles screen to this .png or .ppm file"),
+                ),
+        )
         .get_matches();
 
-    match matches.subcommand_name() {
-        Some("day1-1") => day1::part1(input::as_string("day1").as_ref()),
-        Some("day1-2") => day1::part2(input::as_string("day1").as_ref()),
-        Some("day2-1") => day2::part1(input::parse_comma_separated_ints("day2")),
-        Some("day2-2") => day2::part2(input::parse_comma_separated_ints("day2")),
-        Some("day3-1") => day3::part1(input::as_string("day3").as_ref()),
-        Some("day3-2") => day3::part2(input::as_string("day3").as_ref()),
-        Some("day4-1") => day4::part1(130254, 678275),
-        Some("day4-2") => day4::part2(130254, 678275),
-        None => println!("You need to specify a day to get a solution"),
+    match matches.subcommand() {
+        ("day1-1", _) => day1::part1(input::as_string("day1").as_ref()),
+        ("day1-2", _) => day1::part2(input::as_string("day1").as_ref()),
+        ("day2-1", _) => day2::part1(input::parse_comma_separated_ints("day2")),
+        ("day2-2", _) => day2::part2(input::parse_comma_separated_ints("day2")),
+        ("day3-1", _) => day3::part1(input::as_string("day3").as_ref()),
+        ("day3-2", _) => day3::part2(input::as_string("day3").as_ref()),
+        ("day3", Some(args)) => match args.subcommand() {
+            ("crossings", Some(args)) => match args.value_of("min-wires").unwrap().parse() {
+                Ok(min_wires) => day3::crossings_report(
+                    input::as_string(args.value_of("FILE").unwrap()).as_ref(),
+                    min_wires,
+                ),
+                Err(_) => println!("The minimum number of wires must be a positive integer"),
+            },
+            ("ascii", Some(args)) => {
+                let follow = match args.value_of("follow").map(str::parse) {
+                    Some(Ok(wire)) => Some(wire),
+                    Some(Err(_)) => {
+                        println!("The wire to follow must be a positive integer");
+                        return;
+                    }
+                    None => None,
+                };
+                match args.value_of("scale").unwrap().parse() {
+                    Ok(scale) => day3::ascii::render(
+                        input::as_string(args.value_of("FILE").unwrap()).as_ref(),
+                        args.value_of("viewport"),
+                        scale,
+                        follow,
+                    ),
+                    Err(_) => println!("The scale must be a positive integer"),
+                }
+            }
+            ("steps", Some(args)) => {
+                let visit = match args.value_of("visit").unwrap() {
+                    "latest" => day3::steps::Visit::Latest,
+                    "every" => day3::steps::Visit::Every,
+                    _ => day3::steps::Visit::First,
+                };
+                day3::steps::report(
+                    input::as_string(args.value_of("FILE").unwrap()).as_ref(),
+                    visit,
+                )
+            }
+            ("intersections", Some(args)) => {
+                let sort = match args.value_of("sort").unwrap() {
+                    "distance" => day3::intersections::SortBy::Distance,
+                    "steps" => day3::intersections::SortBy::Steps,
+                    _ => day3::intersections::SortBy::Position,
+                };
+                let format = match args.value_of("format").unwrap() {
+                    "csv" => day3::intersections::Format::Csv,
+                    "json" => day3::intersections::Format::Json,
+                    _ => day3::intersections::Format::Table,
+                };
+                day3::intersections::export(
+                    input::as_string(args.value_of("FILE").unwrap()).as_ref(),
+                    sort,
+                    format,
+                    args.value_of("output"),
+                )
+            }
+            ("closest", Some(args)) => day3::metric::report(
+                input::as_string(args.value_of("FILE").unwrap()).as_ref(),
+                args.value_of("metric").unwrap(),
+                args.value_of("origin").unwrap(),
+            ),
+            ("analyse", Some(args)) => {
+                day3::analysis::report(input::as_string(args.value_of("FILE").unwrap()).as_ref())
+            }
+            ("reroute", Some(args)) => match args.value_of("turn-penalty").unwrap().parse() {
+                Ok(turn_penalty) => day3::reroute::report(
+                    input::as_string(args.value_of("FILE").unwrap()).as_ref(),
+                    turn_penalty,
+                ),
+                Err(_) => println!("The turn penalty must be an integer"),
+            },
+            ("normalise", Some(args)) => day3::normalise::export(
+                input::as_string(args.value_of("FILE").unwrap()).as_ref(),
+                args.value_of("output"),
+            ),
+            ("animate", Some(args)) => match args.value_of("duration").unwrap().parse() {
+                Ok(duration) if duration > 0.0 => day3::svg::animate(
+                    input::as_string(args.value_of("FILE").unwrap()).as_ref(),
+                    duration,
+                    args.value_of("output"),
+                ),
+                _ => println!("The duration must be a positive number of seconds"),
+            },
+            ("query", Some(args)) => day3::index::report(
+                input::as_string(args.value_of("FILE").unwrap()).as_ref(),
+                args.value_of("point"),
+                args.value_of("area"),
+            ),
+            ("render", Some(args)) => day3::svg::render(
+                input::as_string(args.value_of("FILE").unwrap()).as_ref(),
+                args.value_of("output"),
+            ),
+            _ => println!("You need to specify a day 3 tool"),
+        },
+        (part @ "day4-1", Some(args)) | (part @ "day4-2", Some(args)) => {
+            let range = match day4::parse_range(
+                args.value_of("start").unwrap(),
+                args.value_of("end").unwrap(),
+                args.value_of("radix").unwrap(),
+            ) {
+                None => return,
+                Some(range) => range,
+            };
+            match args.value_of("length").unwrap().parse() {
+                Ok(length) if part == "day4-1" => day4::part1(&range, length),
+                Ok(length) => day4::part2(&range, length),
+                Err(_) => println!("The number of digits must be a positive integer"),
+            }
+        }
+        ("intcode-test", Some(args)) => {
+            if !day2::suite::run(args.value_of("DIR").unwrap()) {
+                std::process::exit(1);
+            }
+        }
+        ("day4", Some(args)) => match args.subcommand() {
+            ("count", Some(args)) => {
+                if let Some(range) = day4::parse_range(
+                    args.value_of("start").unwrap(),
+                    args.value_of("end").unwrap(),
+                    args.value_of("radix").unwrap(),
+                ) {
+                    day4::count_report(&range, args.value_of("rules").unwrap())
+                }
+            }
+            (tool @ "list", Some(args)) | (tool @ "tally", Some(args)) => {
+                let pair = match args.value_of("pair").unwrap() {
+                    "adjacent" => day4::count::Pair::Adjacent,
+                    _ => day4::count::Pair::Exact,
+                };
+                let range = match day4::parse_range(
+                    args.value_of("start").unwrap(),
+                    args.value_of("end").unwrap(),
+                    args.value_of("radix").unwrap(),
+                ) {
+                    None => return,
+                    Some(range) => range,
+                };
+                match args.value_of("length").unwrap().parse() {
+                    Ok(length) if tool == "list" => day4::codes::list(&range, length, pair),
+                    Ok(length) => day4::count::report(&range, length, pair),
+                    Err(_) => println!("The number of digits must be a positive integer"),
+                }
+            }
+            _ => println!("You need to specify a day 4 tool"),
+        },
+        ("gen", Some(args)) => match args.subcommand() {
+            ("wires", Some(args)) => {
+                let intersections = match args.value_of("intersections").map(str::parse) {
+                    Some(Ok(target)) => Some(target),
+                    Some(Err(_)) => {
+                        println!("The number of intersections must be a positive integer");
+                        return;
+                    }
+                    None => None,
+                };
+                match (
+                    args.value_of("wires").unwrap().parse(),
+                    args.value_of("segments").unwrap().parse(),
+                    args.value_of("max-length").unwrap().parse(),
+                    args.value_of("seed").unwrap().parse(),
+                ) {
+                    (Ok(wires), Ok(segments), Ok(max_length), Ok(seed)) => day3::generate::run(
+                        &day3::generate::Spec {
+                            wires,
+                            segments,
+                            max_length,
+                            intersections,
+                        },
+                        seed,
+                        args.value_of("output"),
+                    ),
+                    _ => println!(
+                        "The number of wires and segments, the maximum length and the seed must be positive integers"
+                    ),
+                }
+            }
+            _ => println!("You need to specify what to generate"),
+        },
+        ("minimize", Some(args)) => {
+            let inputs = match (args.value_of("noun"), args.value_of("verb")) {
+                (Some(noun), Some(verb)) => match (noun.parse(), verb.parse()) {
+                    (Ok(noun), Ok(verb)) => Some((noun, verb)),
+                    _ => {
+                        println!("The noun and verb must be integers");
+                        return;
+                    }
+                },
+                _ => None,
+            };
+            day2::minimize::run(
+                input::parse_comma_separated_ints(args.value_of("FILE").unwrap()),
+                inputs,
+            )
+        }
+        ("optimize", Some(args)) => {
+            match (
+                args.value_of("trials").unwrap().parse(),
+                args.value_of("seed").unwrap().parse(),
+            ) {
+                (Ok(trials), Ok(seed)) => day2::optimize::run(
+                    input::parse_comma_separated_ints(args.value_of("FILE").unwrap()),
+                    trials,
+                    seed,
+                ),
+                _ => println!("The number of trials and the seed must be positive integers"),
+            }
+        }
+        ("intcode-run", Some(args)) => match args.value_of("seed").unwrap().parse() {
+            Ok(seed) => day2::mmio::run(
+                input::parse_comma_separated_ints(args.value_of("FILE").unwrap()),
+                &args
+                    .values_of("device")
+                    .map(|devices| devices.collect::<Vec<_>>())
+                    .unwrap_or_default(),
+                args.value_of("input").unwrap(),
+                seed,
+                args.value_of("image"),
+            ),
+            Err(_) => println!("The seed must be a positive integer"),
+        },
+        ("", None) => println!("You need to specify a day to get a solution"),
         _ => println!("I don't understand :("),
     }
 }
@@ -0,0 +1,53 @@
+// A small seeded random number generator (SplitMix64). Runs that are given the same seed always
+// produce the same numbers, which keeps randomised checks and generated inputs reproducible.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // Returns a number in the inclusive range `low..=high`.
+    pub fn range(&mut self, low: i64, high: i64) -> i64 {
+        assert!(low <= high, "empty range {}..={}", low, high);
+        let span = (high as i128 - low as i128 + 1) as u128;
+        (low as i128 + (self.next_u64() as u128 % span) as i128) as i64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_numbers() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+        assert_ne!(Rng::new(1).next_u64(), Rng::new(2).next_u64());
+    }
+
+    #[test]
+    fn test_range() {
+        let mut rng = Rng::new(7);
+        for _ in 0..1000 {
+            let n = rng.range(-3, 3);
+            assert!((-3..=3).contains(&n));
+        }
+        assert_eq!(5, rng.range(5, 5));
+        let n = rng.range(i64::MIN, i64::MAX);
+        assert!((i64::MIN..=i64::MAX).contains(&n));
+    }
+}